@@ -0,0 +1,261 @@
+use std::env;
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::error;
+
+use crate::database::SavedRoute;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Request to webhook failed")]
+    Request(#[from] reqwest::Error),
+    #[error("Webhook rejected the alert ({status}): {body}")]
+    Rejected { status: u16, body: String },
+}
+
+/// The shape of the JSON posted to a webhook.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// A message Discord webhooks accept, which also works with Slack-style `content` webhooks.
+    #[default]
+    Discord,
+    /// The alert as structured JSON.
+    Json,
+}
+
+impl WebhookFormat {
+    pub const ALL: [WebhookFormat; 2] = [WebhookFormat::Discord, WebhookFormat::Json];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookFormat::Discord => "discord",
+            WebhookFormat::Json => "json",
+        }
+    }
+
+    pub fn parse(format: &str) -> WebhookFormat {
+        match format {
+            "json" => WebhookFormat::Json,
+            _ => WebhookFormat::Discord,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RouteAlert {
+    /// A system on the route got riskier than the route's threshold.
+    HotSystem {
+        system: String,
+        risk: f64,
+        threshold: f64,
+    },
+    /// A route with less risk than the one last reported appeared.
+    BetterRoute {
+        route: Vec<String>,
+        risk: f64,
+        previous_risk: f64,
+    },
+}
+
+impl RouteAlert {
+    pub fn message(&self, saved_route: &SavedRoute) -> String {
+        let name = &saved_route.name;
+        match self {
+            RouteAlert::HotSystem {
+                system,
+                risk,
+                threshold,
+            } => format!(
+                "{name}: {system} is hot right now (risk {risk:.2}, over the threshold of {threshold:.2})"
+            ),
+            RouteAlert::BetterRoute {
+                route,
+                risk,
+                previous_risk,
+            } => format!(
+                "{name}: a safer route is available (risk {risk:.2} instead of {previous_risk:.2}): {}",
+                route.join(" → ")
+            ),
+        }
+    }
+}
+
+/// What re-evaluating a saved route found, and the state to remember for next time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteEvaluation {
+    pub alerts: Vec<RouteAlert>,
+    /// The route to watch from now on.
+    pub route: Vec<String>,
+    /// The systems on the route that are over the threshold. Systems stay quiet while they stay
+    /// hot and are alerted on again once they cool down and heat back up.
+    pub hot_systems: Vec<String>,
+}
+
+/// Compares a saved route against the current risks. `route_risks` are the risks of each jump of
+/// the route being watched, or of the safest route if none is watched yet, and `safest_risks`
+/// those of the current safest route.
+pub fn evaluate_saved_route(
+    saved_route: &SavedRoute,
+    route_risks: &[f64],
+    safest_route: &[String],
+    safest_risks: &[f64],
+) -> RouteEvaluation {
+    let watched_route = if saved_route.route.is_empty() {
+        safest_route
+    } else {
+        &saved_route.route
+    };
+    let mut alerts = Vec::new();
+
+    let hot_systems: Vec<(String, f64)> = watched_route
+        .iter()
+        .skip(1)
+        .zip(route_risks)
+        .filter(|(_, risk)| **risk > saved_route.threshold)
+        .map(|(system, &risk)| (system.clone(), risk))
+        .collect();
+    for (system, risk) in &hot_systems {
+        if !saved_route.hot_systems.contains(system) {
+            alerts.push(RouteAlert::HotSystem {
+                system: system.clone(),
+                risk: *risk,
+                threshold: saved_route.threshold,
+            });
+        }
+    }
+
+    let route_risk: f64 = route_risks.iter().sum();
+    let safest_risk: f64 = safest_risks.iter().sum();
+    let mut route = watched_route.to_vec();
+    if !saved_route.route.is_empty() && safest_route != watched_route && safest_risk < route_risk {
+        alerts.push(RouteAlert::BetterRoute {
+            route: safest_route.to_vec(),
+            risk: safest_risk,
+            previous_risk: route_risk,
+        });
+        route = safest_route.to_vec();
+    }
+
+    RouteEvaluation {
+        alerts,
+        route,
+        hot_systems: hot_systems.into_iter().map(|(system, _)| system).collect(),
+    }
+}
+
+/// The JSON to post to a webhook for an alert.
+pub fn webhook_payload(saved_route: &SavedRoute, alert: &RouteAlert) -> Value {
+    match saved_route.webhook_format {
+        WebhookFormat::Discord => json!({ "content": alert.message(saved_route) }),
+        WebhookFormat::Json => json!({
+            "saved_route": saved_route.name,
+            "from": saved_route.from_system_name,
+            "to": saved_route.to_system_name,
+            "message": alert.message(saved_route),
+            "alert": alert,
+        }),
+    }
+}
+
+/// The webhook alerts go to when a saved route doesn't have its own, set with the
+/// `ALERT_WEBHOOK_URL` environment variable.
+pub fn default_webhook_url() -> Option<String> {
+    env::var("ALERT_WEBHOOK_URL")
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+}
+
+pub async fn send_alert(
+    client: &Client,
+    url: &str,
+    saved_route: &SavedRoute,
+    alert: &RouteAlert,
+) -> Result<(), Error> {
+    let response = client
+        .post(url)
+        .json(&webhook_payload(saved_route, alert))
+        .send()
+        .await?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let body = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Could not read error body".to_string());
+    error!(
+        "Webhook for {} failed with status {}: {}",
+        saved_route.name, status, body
+    );
+    Err(Error::Rejected {
+        status: status.as_u16(),
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn saved_route(route: &[&str], hot_systems: &[&str]) -> SavedRoute {
+        SavedRoute {
+            name: String::from("Jita run"),
+            from_system_name: String::from("Jita"),
+            to_system_name: String::from("Amarr"),
+            threshold: 1.0,
+            webhook_url: None,
+            webhook_format: WebhookFormat::Discord,
+            route: names(route),
+            hot_systems: names(hot_systems),
+        }
+    }
+
+    #[test]
+    fn test_hot_systems_are_alerted_once() {
+        let route = names(&["Jita", "Uedama", "Amarr"]);
+        let saved = saved_route(&["Jita", "Uedama", "Amarr"], &[]);
+
+        let evaluation = evaluate_saved_route(&saved, &[2.5, 0.1], &route, &[2.5, 0.1]);
+        assert_eq!(
+            evaluation.alerts,
+            vec![RouteAlert::HotSystem {
+                system: String::from("Uedama"),
+                risk: 2.5,
+                threshold: 1.0,
+            }]
+        );
+        assert_eq!(evaluation.hot_systems, vec!["Uedama"]);
+        assert_eq!(
+            webhook_payload(&saved, &evaluation.alerts[0]),
+            json!({ "content": "Jita run: Uedama is hot right now (risk 2.50, over the threshold of 1.00)" })
+        );
+
+        let still_hot = saved_route(&["Jita", "Uedama", "Amarr"], &["Uedama"]);
+        let evaluation = evaluate_saved_route(&still_hot, &[2.5, 0.1], &route, &[2.5, 0.1]);
+        assert!(evaluation.alerts.is_empty());
+    }
+
+    #[test]
+    fn test_better_route_replaces_the_watched_route() {
+        let saved = saved_route(&["Jita", "Uedama", "Amarr"], &[]);
+        let safest = names(&["Jita", "Sivala", "Ala", "Amarr"]);
+
+        let evaluation = evaluate_saved_route(&saved, &[0.1, 0.8], &safest, &[0.1, 0.1, 0.1]);
+
+        assert_eq!(evaluation.route, safest);
+        assert!(matches!(
+            evaluation.alerts.as_slice(),
+            [RouteAlert::BetterRoute { .. }]
+        ));
+    }
+}
@@ -0,0 +1,180 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use neo4rs::Error as Neo4rsError;
+use serde::Serialize;
+
+use crate::sync::now_seconds;
+use crate::{database, esi, sync};
+
+static REQUEST_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// What went wrong with a request, for clients to act on without matching on messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidRequest,
+    UnknownSystem,
+    AmbiguousSystem,
+    NotFound,
+    MethodNotAllowed,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    /// The systems exist but there's no way between them.
+    NoRoute,
+    EsiRateLimited,
+    /// ESI, EVE Scout or zKillboard failed to answer.
+    UpstreamUnavailable,
+    /// A route graph projection hasn't been built yet.
+    ProjectionMissing,
+    DatabaseUnavailable,
+    Internal,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 13] = [
+        ErrorCode::InvalidRequest,
+        ErrorCode::UnknownSystem,
+        ErrorCode::AmbiguousSystem,
+        ErrorCode::NotFound,
+        ErrorCode::MethodNotAllowed,
+        ErrorCode::PayloadTooLarge,
+        ErrorCode::UnsupportedMediaType,
+        ErrorCode::NoRoute,
+        ErrorCode::EsiRateLimited,
+        ErrorCode::UpstreamUnavailable,
+        ErrorCode::ProjectionMissing,
+        ErrorCode::DatabaseUnavailable,
+        ErrorCode::Internal,
+    ];
+
+    pub fn status(&self) -> u16 {
+        match self {
+            ErrorCode::InvalidRequest | ErrorCode::UnknownSystem | ErrorCode::AmbiguousSystem => {
+                400
+            }
+            ErrorCode::NotFound | ErrorCode::NoRoute => 404,
+            ErrorCode::MethodNotAllowed => 405,
+            ErrorCode::PayloadTooLarge => 413,
+            ErrorCode::UnsupportedMediaType => 415,
+            ErrorCode::EsiRateLimited => 429,
+            ErrorCode::UpstreamUnavailable => 502,
+            ErrorCode::ProjectionMissing | ErrorCode::DatabaseUnavailable => 503,
+            ErrorCode::Internal => 500,
+        }
+    }
+}
+
+impl From<&sync::Error> for ErrorCode {
+    fn from(error: &sync::Error) -> Self {
+        match error {
+            sync::Error::EsiSource(esi::Error::RateLimited { .. }) => ErrorCode::EsiRateLimited,
+            sync::Error::EsiSource(_)
+            | sync::Error::EveScoutSource(_)
+            | sync::Error::ZKillboardSource(_) => ErrorCode::UpstreamUnavailable,
+            sync::Error::Target(database::Error::Client(error)) => database_error_code(error),
+            sync::Error::Target(database::Error::MissingConnection(..)) => ErrorCode::NoRoute,
+            sync::Error::Target(database::Error::ProjectionMissing(_)) => {
+                ErrorCode::ProjectionMissing
+            }
+            sync::Error::Target(database::Error::Gds(_) | database::Error::MissingSystem(_))
+            | sync::Error::Process(_) => ErrorCode::Internal,
+        }
+    }
+}
+
+fn database_error_code(error: &Neo4rsError) -> ErrorCode {
+    match error {
+        Neo4rsError::ConnectionError | Neo4rsError::IOError { .. } => {
+            ErrorCode::DatabaseUnavailable
+        }
+        _ => ErrorCode::Internal,
+    }
+}
+
+/// The JSON body of every error response.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub error: String,
+    /// Identifies the request in the logs.
+    pub request_id: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+}
+
+impl ErrorBody {
+    pub fn new(code: ErrorCode, error: impl Into<String>) -> Self {
+        Self {
+            code,
+            error: error.into(),
+            request_id: next_request_id(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    pub fn with_suggestions(mut self, suggestions: Vec<String>) -> Self {
+        self.suggestions = suggestions;
+        self
+    }
+}
+
+/// The time and a count of the requests that came before, so IDs are unique within a run of the
+/// server and rarely collide across runs.
+pub fn next_request_id() -> String {
+    let count = REQUEST_COUNT.fetch_add(1, Ordering::Relaxed);
+    format!("{:08x}-{count:06x}", now_seconds() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_errors_map_to_codes() {
+        let rate_limited = sync::Error::EsiSource(esi::Error::RateLimited {
+            body: String::from("Error limited"),
+        });
+        assert_eq!(ErrorCode::from(&rate_limited), ErrorCode::EsiRateLimited);
+        assert_eq!(ErrorCode::EsiRateLimited.status(), 429);
+
+        let server_error = sync::Error::EsiSource(esi::Error::ServerError {
+            status: 503,
+            body: String::new(),
+        });
+        assert_eq!(
+            ErrorCode::from(&server_error),
+            ErrorCode::UpstreamUnavailable
+        );
+
+        let missing = sync::Error::Target(database::Error::ProjectionMissing(String::from(
+            "jump-risk",
+        )));
+        assert_eq!(ErrorCode::from(&missing), ErrorCode::ProjectionMissing);
+        assert_eq!(ErrorCode::ProjectionMissing.status(), 503);
+
+        let unavailable =
+            sync::Error::Target(database::Error::Client(Neo4rsError::ConnectionError));
+        assert_eq!(
+            ErrorCode::from(&unavailable),
+            ErrorCode::DatabaseUnavailable
+        );
+    }
+
+    #[test]
+    fn test_error_body_serializes_code_and_request_id() {
+        let body = ErrorBody::new(ErrorCode::UnknownSystem, "unknown system: Jitta")
+            .with_suggestions(vec![String::from("Jita")]);
+        let other = ErrorBody::new(ErrorCode::NoRoute, "route not found");
+        assert_ne!(body.request_id, other.request_id);
+
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(json["code"], "unknown_system");
+        assert_eq!(json["suggestions"][0], "Jita");
+        assert!(
+            serde_json::to_value(&other)
+                .unwrap()
+                .get("suggestions")
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::database::{Kill, Stargate};
+
+/// Kills further than this from every stargate, in metres, aren't put down to a gate.
+const GATE_RADIUS_METRES: f64 = 150_000.0;
+
+/// Kills away from a stargate are less likely to be a gate camp than a fight, so they count for
+/// less confidence.
+const OFF_GATE_CONFIDENCE: f64 = 0.7;
+
+/// The risk added to jumping into a system with an active camp when routes avoid camps, which is
+/// high enough that a route only goes through one when there's no other way.
+pub const CAMP_RISK: f64 = 1000.0;
+
+/// How a burst of kills has to look to be flagged as a camp.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CampDetection {
+    /// Kills this many minutes apart or closer belong to the same burst.
+    pub window_minutes: u32,
+    /// The fewest kills in a burst that make a camp.
+    pub min_kills: u32,
+    /// A camp is no longer active once this many minutes pass without a kill.
+    pub active_minutes: u32,
+}
+
+impl Default for CampDetection {
+    fn default() -> Self {
+        Self {
+            window_minutes: 10,
+            min_kills: 3,
+            active_minutes: 45,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Camp {
+    pub system_id: i64,
+    /// The stargate the kills happened at, when killmails say where the victims died.
+    pub stargate_id: Option<i64>,
+    pub stargate_name: Option<String>,
+    pub kills: u32,
+    pub pod_kills: u32,
+    /// The most attackers on any one of the kills.
+    pub max_attackers: u32,
+    pub first_kill: i64,
+    pub last_kill: i64,
+    pub minutes_since_last_kill: f64,
+    /// How likely the burst is an active camp, from 0 to 1. More kills, kills at a gate and recent
+    /// kills make it more likely.
+    pub confidence: f64,
+}
+
+/// The kills at one stargate, or in one system away from its stargates.
+type KillGroup<'a> = (Option<&'a Stargate>, Vec<&'a Kill>);
+
+fn distance(stargate: &Stargate, (x, y, z): (f64, f64, f64)) -> f64 {
+    ((stargate.x - x).powi(2) + (stargate.y - y).powi(2) + (stargate.z - z).powi(2)).sqrt()
+}
+
+/// The stargate a kill happened at, if it happened close enough to one.
+fn nearest_stargate<'a>(stargates: &[&'a Stargate], kill: &Kill) -> Option<&'a Stargate> {
+    let position = kill.position?;
+    stargates
+        .iter()
+        .map(|stargate| (*stargate, distance(stargate, position)))
+        .filter(|(_, distance)| *distance <= GATE_RADIUS_METRES)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(stargate, _)| stargate)
+}
+
+impl CampDetection {
+    /// Finds the active camps among the given kills, most confident first. Kills are grouped by
+    /// the stargate they happened at, or by system when they can't be placed at a gate, and the
+    /// latest burst of each group is a camp if it has enough kills and is recent enough. `now` is
+    /// in seconds since the epoch.
+    pub fn detect(&self, kills: &[Kill], stargates: &[Stargate], now: i64) -> Vec<Camp> {
+        let mut stargates_in_system: HashMap<i64, Vec<&Stargate>> = HashMap::new();
+        for stargate in stargates {
+            stargates_in_system
+                .entry(stargate.system_id)
+                .or_default()
+                .push(stargate);
+        }
+
+        let mut groups: HashMap<(i64, Option<i64>), KillGroup> = HashMap::new();
+        for kill in kills {
+            let stargate = stargates_in_system
+                .get(&kill.system_id)
+                .and_then(|stargates| nearest_stargate(stargates, kill));
+            groups
+                .entry((kill.system_id, stargate.map(|s| s.stargate_id)))
+                .or_insert_with(|| (stargate, Vec::new()))
+                .1
+                .push(kill);
+        }
+
+        let mut camps: Vec<Camp> = groups
+            .into_iter()
+            .filter_map(|((system_id, _), (stargate, kills))| {
+                self.latest_burst(system_id, stargate, kills, now)
+            })
+            .collect();
+        camps.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        camps
+    }
+
+    fn latest_burst(
+        &self,
+        system_id: i64,
+        stargate: Option<&Stargate>,
+        mut kills: Vec<&Kill>,
+        now: i64,
+    ) -> Option<Camp> {
+        kills.sort_by_key(|kill| kill.time);
+        let window_seconds = i64::from(self.window_minutes) * 60;
+        let mut start = kills.len().checked_sub(1)?;
+        while start > 0 && kills[start].time - kills[start - 1].time <= window_seconds {
+            start -= 1;
+        }
+        let burst = &kills[start..];
+
+        let last_kill = burst.last()?.time;
+        let minutes_since_last_kill = (now - last_kill).max(0) as f64 / 60.0;
+        if (burst.len() as u32) < self.min_kills
+            || minutes_since_last_kill > f64::from(self.active_minutes)
+        {
+            return None;
+        }
+
+        let count_score = (burst.len() as f64 / (2.0 * f64::from(self.min_kills))).min(1.0);
+        let location_score = if stargate.is_some() {
+            1.0
+        } else {
+            OFF_GATE_CONFIDENCE
+        };
+        let recency_score = 0.5_f64.powf(minutes_since_last_kill / f64::from(self.window_minutes));
+
+        Some(Camp {
+            system_id,
+            stargate_id: stargate.map(|s| s.stargate_id),
+            stargate_name: stargate.map(|s| s.name.clone()),
+            kills: burst.len() as u32,
+            pod_kills: burst.iter().filter(|kill| kill.pod_kill).count() as u32,
+            max_attackers: burst
+                .iter()
+                .map(|kill| kill.attacker_count)
+                .max()
+                .unwrap_or_default(),
+            first_kill: burst[0].time,
+            last_kill,
+            minutes_since_last_kill,
+            confidence: count_score * location_score * recency_score,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stargate(stargate_id: i64, x: f64) -> Stargate {
+        Stargate {
+            destination_stargate_id: stargate_id + 1,
+            destination_system_id: 30002814,
+            name: format!("Stargate ({stargate_id})"),
+            x,
+            y: 0.0,
+            z: 0.0,
+            stargate_id,
+            system_id: 30002813,
+            type_id: 29624,
+        }
+    }
+
+    fn kill(killmail_id: i64, time: i64, x: Option<f64>) -> Kill {
+        Kill {
+            killmail_id,
+            system_id: 30002813,
+            time,
+            ship_type_id: 648,
+            pod_kill: killmail_id % 2 == 0,
+            attacker_count: 8,
+            position: x.map(|x| (x, 0.0, 0.0)),
+        }
+    }
+
+    #[test]
+    fn test_burst_at_a_gate_is_a_camp() {
+        let stargates = [stargate(1, 0.0), stargate(2, 1.0e10)];
+        let kills = [
+            kill(1, 1000, Some(10_000.0)),
+            kill(2, 1200, Some(20_000.0)),
+            kill(3, 1500, Some(5_000.0)),
+            // Far from both gates.
+            kill(4, 1500, Some(5.0e9)),
+        ];
+
+        let camps = CampDetection::default().detect(&kills, &stargates, 1500);
+
+        assert_eq!(camps.len(), 1);
+        assert_eq!(camps[0].stargate_id, Some(1));
+        assert_eq!(camps[0].kills, 3);
+        assert_eq!(camps[0].pod_kills, 1);
+        assert_eq!(camps[0].first_kill, 1000);
+        assert_eq!(camps[0].confidence, 0.5);
+    }
+
+    #[test]
+    fn test_spread_out_or_old_kills_are_not_a_camp() {
+        let detection = CampDetection::default();
+        // An hour between kills.
+        let spread_out = [kill(1, 0, None), kill(2, 3600, None), kill(3, 7200, None)];
+        assert!(detection.detect(&spread_out, &[], 7200).is_empty());
+
+        // A burst that ended two hours ago.
+        let old = [kill(1, 0, None), kill(2, 60, None), kill(3, 120, None)];
+        assert!(detection.detect(&old, &[], 7320).is_empty());
+
+        let recent = detection.detect(&old, &[], 120);
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].stargate_id, None);
+        assert_eq!(recent[0].confidence, 0.5 * OFF_GATE_CONFIDENCE);
+    }
+}
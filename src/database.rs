@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::env;
 use std::sync::Arc;
 use std::time::Duration;
@@ -6,6 +7,9 @@ use neo4rs::{DeError, Error as Neo4rsError, Graph, Row, query};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 
+use crate::alerts::WebhookFormat;
+use crate::risk::{RiskModelKind, SystemActivity};
+
 #[derive(Debug, thiserror::Error)]
 #[error("GDS procedure call '{0}' did not return the expected row")]
 pub struct GdsProcedureError(&'static str);
@@ -16,6 +20,12 @@ pub enum Error {
     Client(#[from] Neo4rsError),
     #[error(transparent)]
     Gds(#[from] GdsProcedureError),
+    #[error("no connection from {0} to {1}")]
+    MissingConnection(String, String),
+    #[error("the {0} route graph hasn't been built yet")]
+    ProjectionMissing(String),
+    #[error("no system named {0}")]
+    MissingSystem(String),
 }
 
 pub async fn get_graph_client_with_retry(
@@ -78,7 +88,7 @@ pub async fn stargate_id_exists(graph: Arc<Graph>, stargate_id: i64) -> Result<b
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct System {
     pub constellation_id: i64,
     pub name: String,
@@ -92,7 +102,84 @@ pub struct System {
     pub stargates: Vec<i64>,
     pub system_id: i64,
     pub kills: u32,
+    #[serde(default)]
+    pub pod_kills: u32,
+    #[serde(default)]
+    pub npc_kills: u32,
     pub jumps: u32,
+    #[serde(default)]
+    pub region_id: Option<i64>,
+    #[serde(default)]
+    pub region_name: Option<String>,
+    #[serde(default)]
+    pub constellation_name: Option<String>,
+    /// The risk of jumping into the system, as of the last risk refresh.
+    #[serde(default)]
+    pub risk: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityBand {
+    High,
+    Low,
+    Null,
+}
+
+/// The lowest security status of high-sec. Security status is displayed rounded to one decimal, so
+/// anything from 0.45 shows as 0.5.
+pub const HIGH_SEC_MIN_STATUS: f64 = 0.45;
+
+/// Systems with a security status above this and below high-sec are low-sec, the rest null-sec.
+pub const LOW_SEC_ABOVE_STATUS: f64 = 0.0;
+
+impl SecurityBand {
+    pub const ALL: [SecurityBand; 3] = [SecurityBand::High, SecurityBand::Low, SecurityBand::Null];
+
+    pub fn from_status(security_status: f64) -> SecurityBand {
+        if security_status >= HIGH_SEC_MIN_STATUS {
+            SecurityBand::High
+        } else if security_status > LOW_SEC_ABOVE_STATUS {
+            SecurityBand::Low
+        } else {
+            SecurityBand::Null
+        }
+    }
+}
+
+impl System {
+    pub fn is_high_sec(&self) -> bool {
+        self.security_band() == SecurityBand::High
+    }
+
+    pub fn security_band(&self) -> SecurityBand {
+        SecurityBand::from_status(self.security_status)
+    }
+}
+
+/// Narrows down a set of systems by their security, region and risk.
+#[derive(Debug, Clone, Default)]
+pub struct SystemFilter {
+    pub security: Option<SecurityBand>,
+    /// Matches the region's name, ignoring case.
+    pub region: Option<String>,
+    pub max_risk: Option<f64>,
+}
+
+impl SystemFilter {
+    pub fn matches(&self, system: &System) -> bool {
+        self.security
+            .is_none_or(|security| system.security_band() == security)
+            && self.region.as_ref().is_none_or(|region| {
+                system
+                    .region_name
+                    .as_ref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(region))
+            })
+            && self
+                .max_risk
+                .is_none_or(|max_risk| system.risk.is_some_and(|risk| risk <= max_risk))
+    }
 }
 
 pub async fn save_system(graph: &Arc<Graph>, system: &System) -> Result<(), Error> {
@@ -110,6 +197,8 @@ pub async fn save_system(graph: &Arc<Graph>, system: &System) -> Result<(), Erro
             planets: $planets,
             stargates: $stargates,
             kills: $kills,
+            pod_kills: 0,
+            npc_kills: 0,
             jumps: $jumps
         })";
 
@@ -147,6 +236,106 @@ pub async fn get_system(graph: Arc<Graph>, system_id: i64) -> Result<Option<Syst
     }
 }
 
+/// The systems with the given IDs, in no particular order. IDs without a system are left out.
+pub async fn get_systems_by_id(
+    graph: Arc<Graph>,
+    system_ids: Vec<i64>,
+) -> Result<Vec<System>, Error> {
+    let get_systems_statement = "\
+        MATCH (system:System)
+        WHERE system.system_id IN $system_ids
+        RETURN system";
+    let mut result = graph
+        .execute(query(get_systems_statement).param("system_ids", system_ids))
+        .await?;
+    let mut systems = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        systems.push(row.get("system")?);
+    }
+
+    Ok(systems)
+}
+
+/// Finds a system by name, ignoring case.
+pub async fn find_system_by_name(graph: Arc<Graph>, name: &str) -> Result<Option<System>, Error> {
+    let find_system_statement = "
+        MATCH (system:System)
+        WHERE toLower(system.name) = toLower($name)
+        RETURN system LIMIT 1";
+    let mut result = graph
+        .execute(query(find_system_statement).param("name", name))
+        .await?;
+
+    match result.next().await? {
+        Some(row) => Ok(row.get("system")?),
+        None => Ok(None),
+    }
+}
+
+/// The names of the systems a system ID, or a system name ignoring case, could mean.
+pub async fn find_system_names(graph: Arc<Graph>, input: &str) -> Result<Vec<String>, Error> {
+    let find_names_statement = "
+        MATCH (system:System)
+        WHERE system.system_id = $system_id
+            OR ($system_id IS NULL AND toLower(system.name) = toLower($name))
+        RETURN system.name AS name";
+    let mut result = graph
+        .execute(
+            query(find_names_statement)
+                .param("system_id", input.parse::<i64>().ok())
+                .param("name", input),
+        )
+        .await?;
+    let mut names = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        names.push(row.get("name")?);
+    }
+
+    Ok(names)
+}
+
+/// Lists the ID and name of every system, for searching by name.
+pub async fn get_system_names(graph: Arc<Graph>) -> Result<Vec<(i64, String)>, Error> {
+    let get_names_statement = "MATCH (s:System) RETURN s.system_id AS system_id, s.name AS name";
+    let mut result = graph.execute(query(get_names_statement)).await?;
+    let mut names = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        if let (Ok(system_id), Ok(name)) = (row.get("system_id"), row.get("name")) {
+            names.push((system_id, name));
+        }
+    }
+
+    Ok(names)
+}
+
+/// The ID of every system of a route, in order, along with its name.
+pub async fn get_route_system_ids(
+    graph: Arc<Graph>,
+    route: &[String],
+) -> Result<Vec<(i64, String)>, Error> {
+    let get_ids_statement = "\
+        UNWIND $names AS name
+        OPTIONAL MATCH (system:System {name: name})
+        RETURN name, system.system_id AS system_id";
+    let mut result = graph
+        .execute(query(get_ids_statement).param("names", route.to_vec()))
+        .await?;
+    let mut systems = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        let name: String = row.get("name")?;
+        match row.get::<Option<i64>>("system_id")? {
+            Some(system_id) => systems.push((system_id, name)),
+            None => return Err(Error::MissingSystem(name)),
+        }
+    }
+
+    Ok(systems)
+}
+
 pub async fn get_all_systems(graph: Arc<Graph>) -> Result<Vec<System>, Error> {
     let get_all_systems_statement = "MATCH (s:System) RETURN s as system";
     let mut result = graph.execute(query(get_all_systems_statement)).await?;
@@ -161,6 +350,48 @@ pub async fn get_all_systems(graph: Arc<Graph>) -> Result<Vec<System>, Error> {
     Ok(systems)
 }
 
+pub async fn set_system_regions(
+    graph: Arc<Graph>,
+    region_id: i64,
+    region_name: String,
+    constellation_ids: Vec<i64>,
+) -> Result<(), Error> {
+    let set_regions_statement = "
+        MATCH (s:System)
+        WHERE s.constellation_id IN $constellation_ids
+        SET s.region_id = $region_id, s.region_name = $region_name";
+
+    graph
+        .run(
+            query(set_regions_statement)
+                .param("constellation_ids", constellation_ids)
+                .param("region_id", region_id)
+                .param("region_name", region_name),
+        )
+        .await?;
+    Ok(())
+}
+
+pub async fn set_constellation_names(
+    graph: Arc<Graph>,
+    constellation_ids: Vec<i64>,
+    constellation_names: Vec<String>,
+) -> Result<(), Error> {
+    let set_names_statement = "
+        UNWIND range(0, size($constellation_ids) - 1) AS i
+        MATCH (s:System {constellation_id: $constellation_ids[i]})
+        SET s.constellation_name = $constellation_names[i]";
+
+    graph
+        .run(
+            query(set_names_statement)
+                .param("constellation_ids", constellation_ids)
+                .param("constellation_names", constellation_names),
+        )
+        .await?;
+    Ok(())
+}
+
 pub async fn get_all_system_ids(graph: Arc<Graph>) -> Result<Vec<i64>, Error> {
     let get_all_system_ids_statement = "MATCH (s:System) RETURN s.system_id AS system_id";
     let mut result = graph.execute(query(get_all_system_ids_statement)).await?;
@@ -196,7 +427,51 @@ pub async fn get_saved_stargate_count(graph: &Arc<Graph>) -> Result<i64, Error>
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GateType {
+    /// Connects two systems in the same constellation.
+    System,
+    /// Connects two constellations in the same region.
+    Constellation,
+    /// Connects two regions.
+    Region,
+}
+
+impl GateType {
+    pub const ALL: [GateType; 3] = [GateType::System, GateType::Constellation, GateType::Region];
+
+    /// The gate type of a stargate type, from the last word of its name, such as
+    /// "Stargate (Caldari Region)".
+    pub fn from_type_name(type_name: &str) -> Option<GateType> {
+        let kind = type_name.trim_end_matches(')').split_whitespace().last()?;
+        match kind {
+            "System" => Some(GateType::System),
+            "Constellation" => Some(GateType::Constellation),
+            "Region" => Some(GateType::Region),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            GateType::System => "system",
+            GateType::Constellation => "constellation",
+            GateType::Region => "region",
+        }
+    }
+
+    /// The relationship type stargate jumps of this type get in the route projections.
+    fn projected_relationship_type(&self) -> &'static str {
+        match self {
+            GateType::System => "SYSTEM_GATE",
+            GateType::Constellation => "CONSTELLATION_GATE",
+            GateType::Region => "REGION_GATE",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Stargate {
     pub destination_stargate_id: i64,
     pub destination_system_id: i64,
@@ -238,19 +513,420 @@ pub async fn save_stargate(graph: Arc<Graph>, stargate: &Stargate) -> Result<(),
         )
         .await?;
 
-    create_system_jump_if_missing(graph, stargate.system_id, stargate.destination_system_id)
+    create_stargate_jump(graph, stargate).await
+}
+
+pub async fn get_all_stargates(graph: Arc<Graph>) -> Result<Vec<Stargate>, Error> {
+    let get_all_stargates_statement = "MATCH (sg:Stargate) RETURN sg AS stargate";
+    let mut result = graph.execute(query(get_all_stargates_statement)).await?;
+    let mut stargates = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        if let Ok(stargate) = row.get("stargate") {
+            stargates.push(stargate);
+        }
+    }
+
+    Ok(stargates)
+}
+
+pub async fn get_system_stargates(
+    graph: Arc<Graph>,
+    system_id: i64,
+) -> Result<Vec<Stargate>, Error> {
+    let get_stargates_statement =
+        "MATCH (sg:Stargate {system_id: $system_id}) RETURN sg AS stargate ORDER BY sg.name";
+    let mut result = graph
+        .execute(query(get_stargates_statement).param("system_id", system_id))
+        .await?;
+    let mut stargates = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        stargates.push(row.get("stargate")?);
+    }
+
+    Ok(stargates)
+}
+
+/// Looks up the stargate taken out of each system along a route and the one arrived at in the
+/// next system. Hops with no stargate between them, such as wormholes, are None.
+pub async fn get_route_stargates(
+    graph: Arc<Graph>,
+    system_names: Vec<String>,
+) -> Result<Vec<Option<(Stargate, Stargate)>>, Error> {
+    let mut hops = vec![None; system_names.len().saturating_sub(1)];
+    let route_stargates_statement = "
+        UNWIND range(0, size($system_names) - 2) AS hop
+        MATCH (from:System {name: $system_names[hop]})
+        MATCH (to:System {name: $system_names[hop + 1]})
+        MATCH (departure:Stargate {system_id: from.system_id, destination_system_id: to.system_id})
+        MATCH (arrival:Stargate {stargate_id: departure.destination_stargate_id})
+        RETURN hop, departure, arrival";
+    let mut result = graph
+        .execute(query(route_stargates_statement).param("system_names", system_names))
         .await?;
-    Ok(())
+
+    while let Some(row) = result.next().await? {
+        if let (Ok(hop), Ok(departure), Ok(arrival)) = (
+            row.get::<i64>("hop"),
+            row.get::<Stargate>("departure"),
+            row.get::<Stargate>("arrival"),
+        ) && let Some(slot) = hops.get_mut(hop as usize)
+        {
+            slot.get_or_insert((departure, arrival));
+        }
+    }
+
+    Ok(hops)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionKind {
+    Stargate,
+    Wormhole,
+    JumpBridge,
+}
+
+impl ConnectionKind {
+    pub const ALL: [ConnectionKind; 3] = [
+        ConnectionKind::Stargate,
+        ConnectionKind::Wormhole,
+        ConnectionKind::JumpBridge,
+    ];
+}
+
+/// One hop of a route, with the stargate to take when it's a stargate jump.
+#[derive(Debug, Serialize)]
+pub struct RouteLeg {
+    pub from: String,
+    pub to: String,
+    pub kind: ConnectionKind,
+    pub stargate_id: Option<i64>,
+    pub stargate_name: Option<String>,
+    pub gate_type: Option<GateType>,
+}
+
+/// Describes how to get from each system of a route to the next. Where systems are connected in
+/// more than one way, stargates are preferred over jump bridges and wormholes.
+pub async fn get_route_legs(
+    graph: Arc<Graph>,
+    system_names: Vec<String>,
+) -> Result<Vec<RouteLeg>, Error> {
+    let route_legs_statement = "
+        UNWIND range(0, size($system_names) - 2) AS hop
+        MATCH (from:System {name: $system_names[hop]})-[r:JUMP|BRIDGE|WORMHOLE]->(to:System {name: $system_names[hop + 1]})
+        OPTIONAL MATCH (sg:Stargate {stargate_id: r.stargate_id})
+        WITH hop, from, to, r, sg
+        ORDER BY hop, CASE type(r) WHEN 'JUMP' THEN 0 WHEN 'BRIDGE' THEN 1 ELSE 2 END
+        WITH hop, head(collect({from: from.name, to: to.name, kind: type(r), stargate_id: r.stargate_id, stargate_name: sg.name, gate_type: r.gate_type})) AS leg
+        RETURN leg.from AS from, leg.to AS to, leg.kind AS kind, leg.stargate_id AS stargate_id,
+               leg.stargate_name AS stargate_name, leg.gate_type AS gate_type
+        ORDER BY hop";
+    let mut result = graph
+        .execute(query(route_legs_statement).param("system_names", system_names))
+        .await?;
+    let mut legs = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        legs.push(RouteLeg {
+            from: row.get("from")?,
+            to: row.get("to")?,
+            kind: connection_kind(&row.get::<String>("kind")?),
+            stargate_id: row.get("stargate_id")?,
+            stargate_name: row.get("stargate_name")?,
+            gate_type: gate_type(row.get::<Option<String>>("gate_type")?.as_deref()),
+        });
+    }
+
+    Ok(legs)
+}
+
+/// A system one jump away, and how it's reached.
+#[derive(Debug, Clone, Serialize)]
+pub struct Neighbor {
+    pub system_id: i64,
+    pub name: String,
+    pub kind: ConnectionKind,
+    pub gate_type: Option<GateType>,
+}
+
+/// Lists the systems one stargate, jump bridge or wormhole jump away from a system.
+pub async fn get_system_neighbors(
+    graph: Arc<Graph>,
+    system_id: i64,
+) -> Result<Vec<Neighbor>, Error> {
+    let neighbors_statement = "
+        MATCH (:System {system_id: $system_id})-[r:JUMP|BRIDGE|WORMHOLE]->(n:System)
+        RETURN DISTINCT n.system_id AS system_id, n.name AS name, type(r) AS kind,
+               r.gate_type AS gate_type
+        ORDER BY name";
+    let mut result = graph
+        .execute(query(neighbors_statement).param("system_id", system_id))
+        .await?;
+    let mut neighbors = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        neighbors.push(Neighbor {
+            system_id: row.get("system_id")?,
+            name: row.get("name")?,
+            kind: connection_kind(&row.get::<String>("kind")?),
+            gate_type: gate_type(row.get::<Option<String>>("gate_type")?.as_deref()),
+        });
+    }
+
+    Ok(neighbors)
+}
+
+fn connection_kind(relationship_type: &str) -> ConnectionKind {
+    match relationship_type {
+        "BRIDGE" => ConnectionKind::JumpBridge,
+        "WORMHOLE" => ConnectionKind::Wormhole,
+        _ => ConnectionKind::Stargate,
+    }
+}
+
+fn gate_type(gate_type: Option<&str>) -> Option<GateType> {
+    match gate_type {
+        Some("region") => Some(GateType::Region),
+        Some("constellation") => Some(GateType::Constellation),
+        Some("system") => Some(GateType::System),
+        _ => None,
+    }
+}
+
+pub async fn get_stargate_connections(graph: Arc<Graph>) -> Result<Vec<(i64, i64)>, Error> {
+    let get_connections_statement = "
+        MATCH (sg:Stargate)
+        RETURN sg.system_id AS system_id, sg.destination_system_id AS destination_system_id";
+    let mut result = graph.execute(query(get_connections_statement)).await?;
+    let mut connections = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        if let (Ok(system_id), Ok(destination_system_id)) =
+            (row.get("system_id"), row.get("destination_system_id"))
+        {
+            connections.push((system_id, destination_system_id));
+        }
+    }
+
+    Ok(connections)
+}
+
+/// Lists every connection between two systems that a route with these options could use.
+pub async fn get_system_connections(
+    graph: Arc<Graph>,
+    options: &RouteOptions,
+) -> Result<Vec<(i64, i64)>, Error> {
+    get_system_connections_for_ship(graph, options, None).await
+}
+
+/// Lists every connection a route with these options could use, leaving out the wormholes too
+/// small for a ship of the given size. Wormholes of unknown size are assumed to fit.
+pub async fn get_system_connections_for_ship(
+    graph: Arc<Graph>,
+    options: &RouteOptions,
+    ship_size: Option<ShipSize>,
+) -> Result<Vec<(i64, i64)>, Error> {
+    let get_connections_statement = "
+        MATCH (a:System)-[r]->(b:System)
+        WHERE type(r) IN $relationship_types
+            AND NOT (type(r) = 'JUMP' AND coalesce(r.gate_type, 'system') IN $avoid_gates)
+            AND NOT (type(r) = 'WORMHOLE' AND $wormhole_sizes IS NOT NULL
+                AND r.max_ship_size IS NOT NULL AND NOT r.max_ship_size IN $wormhole_sizes)
+        RETURN a.system_id AS system_id, b.system_id AS destination_system_id";
+    let mut result = graph
+        .execute(
+            query(get_connections_statement)
+                .param("relationship_types", options.relationship_types())
+                .param("avoid_gates", options.avoided_gate_types())
+                .param(
+                    "wormhole_sizes",
+                    ship_size.map(|ship_size| ship_size.fits_through()),
+                ),
+        )
+        .await?;
+    let mut connections = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        if let (Ok(system_id), Ok(destination_system_id)) =
+            (row.get("system_id"), row.get("destination_system_id"))
+        {
+            connections.push((system_id, destination_system_id));
+        }
+    }
+
+    Ok(connections)
+}
+
+/// Lists the wormhole connections in the graph by the names of the systems they join.
+pub async fn get_wormhole_connections(graph: Arc<Graph>) -> Result<Vec<(String, String)>, Error> {
+    let get_wormholes_statement = "
+        MATCH (a:System)-[:WORMHOLE]->(b:System)
+        RETURN DISTINCT a.name AS from_system_name, b.name AS to_system_name";
+    let mut result = graph.execute(query(get_wormholes_statement)).await?;
+    let mut connections = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        connections.push((row.get("from_system_name")?, row.get("to_system_name")?));
+    }
+
+    Ok(connections)
 }
 
 pub async fn save_wormhole(
     graph: Arc<Graph>,
     in_system_id: i64,
     out_system_id: i64,
+    max_ship_size: Option<String>,
 ) -> Result<(), Error> {
     debug!("Saving wormhole from {} to {}", in_system_id, out_system_id);
-    create_system_jump(graph.clone(), in_system_id, out_system_id).await?;
-    create_system_jump(graph.clone(), out_system_id, in_system_id).await
+    create_wormhole_jump(
+        graph.clone(),
+        in_system_id,
+        out_system_id,
+        max_ship_size.clone(),
+    )
+    .await?;
+    create_wormhole_jump(graph.clone(), out_system_id, in_system_id, max_ship_size).await
+}
+
+/// Wormholes are stored as `WORMHOLE` relationships so routes can choose whether to use them.
+/// They take on the risk of the other jumps into the destination, since they're usually created
+/// after the last risk refresh.
+async fn create_wormhole_jump(
+    graph: Arc<Graph>,
+    source_system: i64,
+    dest_system: i64,
+    max_ship_size: Option<String>,
+) -> Result<(), Error> {
+    let create_wormhole_statement = "\
+        MATCH (source:System {system_id: $source_system_id})
+        MATCH (dest:System {system_id: $dest_system_id})
+        CREATE (source)-[:WORMHOLE {
+            cost: 1,
+            max_ship_size: $max_ship_size,
+            risk: coalesce(dest.risk, head([(:System)-[j:JUMP]->(dest) | j.risk]), 0.0)
+        }]->(dest)";
+
+    graph
+        .run(
+            query(create_wormhole_statement)
+                .param("source_system_id", source_system)
+                .param("dest_system_id", dest_system)
+                .param("max_ship_size", max_ship_size),
+        )
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JumpBridge {
+    pub from_system_name: String,
+    pub to_system_name: String,
+    pub owner: Option<String>,
+    pub structure_id: Option<i64>,
+}
+
+/// Saves a jump bridge as a pair of `BRIDGE` relationships, one in each direction, replacing any
+/// bridge already registered between the two systems. The bridge takes on the risk of the jumps
+/// into each system. Returns false if either system is unknown.
+/// Cypher setting every risk model's property on a bridge to the risk of jumping into `system`,
+/// taken from any stargate jump into it.
+fn bridge_risks(bridge: &str, system: &str) -> String {
+    RiskModelKind::ALL
+        .iter()
+        .map(|kind| {
+            let property = kind.property();
+            format!(
+                "{bridge}.{property} = coalesce(head([(:System)-[j:JUMP]->({system}) | j.{property}]), 0.0)"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",\n            ")
+}
+
+pub async fn save_jump_bridge(graph: Arc<Graph>, bridge: &JumpBridge) -> Result<bool, Error> {
+    let save_bridge_statement = format!(
+        "
+        MATCH (a:System {{name: $from_system_name}})
+        MATCH (b:System {{name: $to_system_name}})
+        MERGE (a)-[there:BRIDGE]->(b)
+        MERGE (b)-[back:BRIDGE]->(a)
+        SET there.cost = 1,
+            there.owner = $owner,
+            there.structure_id = $structure_id,
+            {},
+            back.cost = 1,
+            back.owner = $owner,
+            back.structure_id = $structure_id,
+            {}
+        RETURN COUNT(there) AS count",
+        bridge_risks("there", "b"),
+        bridge_risks("back", "a"),
+    );
+
+    let mut result = graph
+        .execute(
+            query(&save_bridge_statement)
+                .param("from_system_name", bridge.from_system_name.clone())
+                .param("to_system_name", bridge.to_system_name.clone())
+                .param("owner", bridge.owner.clone())
+                .param("structure_id", bridge.structure_id),
+        )
+        .await?;
+
+    match result.next().await? {
+        Some(row) => Ok(row_count_is_positive(row)),
+        None => Ok(false),
+    }
+}
+
+pub async fn get_jump_bridges(graph: Arc<Graph>) -> Result<Vec<JumpBridge>, Error> {
+    // Each bridge is stored in both directions, so only return one of them.
+    let get_bridges_statement = "
+        MATCH (a:System)-[r:BRIDGE]->(b:System)
+        WHERE a.name < b.name
+        RETURN a.name AS from_system_name,
+               b.name AS to_system_name,
+               r.owner AS owner,
+               r.structure_id AS structure_id
+        ORDER BY from_system_name";
+    let mut result = graph.execute(query(get_bridges_statement)).await?;
+    let mut bridges = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        if let Ok(bridge) = row.to::<JumpBridge>() {
+            bridges.push(bridge);
+        }
+    }
+
+    Ok(bridges)
+}
+
+/// Removes the bridge between two systems in both directions, returning whether there was one.
+pub async fn remove_jump_bridge(
+    graph: Arc<Graph>,
+    from_system_name: String,
+    to_system_name: String,
+) -> Result<bool, Error> {
+    let remove_bridge_statement = "
+        MATCH (:System {name: $from_system_name})-[r:BRIDGE]-(:System {name: $to_system_name})
+        DELETE r
+        RETURN count(*) AS removed";
+
+    let mut result = graph
+        .execute(
+            query(remove_bridge_statement)
+                .param("from_system_name", from_system_name)
+                .param("to_system_name", to_system_name),
+        )
+        .await?;
+
+    match result.next().await? {
+        Some(row) => Ok(row.get::<i64>("removed")? > 0),
+        None => Ok(false),
+    }
 }
 
 pub async fn set_last_hour_system_jumps(
@@ -276,109 +952,597 @@ pub async fn set_last_hour_system_kills(
     graph: Arc<Graph>,
     system_id: i64,
     kills: i32,
+    pod_kills: i32,
+    npc_kills: i32,
 ) -> Result<(), Error> {
     let set_system_kills_statement = "
         MATCH (s:System {system_id: $system_id})
-        SET s.kills = $kills";
+        SET s.kills = $kills, s.pod_kills = $pod_kills, s.npc_kills = $npc_kills";
 
     graph
         .run(
             query(set_system_kills_statement)
                 .param("system_id", system_id)
-                .param("kills", kills),
+                .param("kills", kills)
+                .param("pod_kills", pod_kills)
+                .param("npc_kills", npc_kills),
         )
         .await?;
     Ok(())
 }
 
+/// Sets the risk of jumping into a system under every risk model, given its activity over the
+/// risk window.
 pub async fn set_system_jump_risk(
     graph: Arc<Graph>,
     system_id: i64,
+    activity: SystemActivity,
     baseline_jump_risk: f64,
 ) -> Result<(), Error> {
-    let system = match get_system(graph.clone(), system_id).await {
-        Ok(Some(system)) => system,
-        Ok(None) => return Ok(()),
-        Err(e) => {
-            warn!("System could not be retrieved when trying to set jump risk {system_id}: {e:?}");
-            return Err(e);
-        }
-    };
-
-    let total_risk = calculate_total_risk(system.kills, system.jumps, baseline_jump_risk);
+    let risks: HashMap<String, f64> = RiskModelKind::ALL
+        .iter()
+        .map(|kind| {
+            let risk = kind.model().risk(&activity, baseline_jump_risk);
+            (kind.property().to_string(), risk)
+        })
+        .collect();
+    let total_risk = risks[RiskModelKind::KillsPerJump.property()];
 
     debug!("Setting jump risks into system {system_id} as {total_risk}");
     let set_system_risk = "
-         MATCH (otherSystem)-[r:JUMP]->(s:System {system_id: $system_id})
-         SET r.risk = $risk";
+         MATCH (s:System {system_id: $system_id})
+         SET s.risk = $risk
+         WITH s
+         MATCH (otherSystem)-[r:JUMP|BRIDGE|WORMHOLE]->(s)
+         SET r += $risks";
     graph
         .run(
             query(set_system_risk)
                 .param("system_id", system_id)
-                .param("risk", total_risk),
+                .param("risk", total_risk)
+                .param("risks", risks),
         )
         .await?;
     Ok(())
 }
 
-fn calculate_total_risk(kills: u32, jumps: u32, baseline_jump_risk: f64) -> f64 {
-    let kills_squared = u32::pow(kills, 2);
-    let system_jump_risk: f64 = if jumps > 0 {
-        kills_squared as f64 / jumps as f64
-    } else {
-        kills_squared.into()
-    };
-    system_jump_risk + baseline_jump_risk
+/// The kills and jumps ESI reported for a system over one hour, which starts at `hour` seconds
+/// since the epoch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActivitySample {
+    pub system_id: i64,
+    pub hour: i64,
+    pub kills: u32,
+    pub pod_kills: u32,
+    pub npc_kills: u32,
+    pub jumps: u32,
 }
 
-async fn jump_exists(
+/// Creates the indexes the hourly activity history and the kills from the kill feed are looked
+/// up by, unless they already exist.
+pub async fn create_indexes(graph: Arc<Graph>) -> Result<(), Error> {
+    let create_index_statements = [
+        "CREATE INDEX activity_sample_system_hour IF NOT EXISTS
+         FOR (a:ActivitySample) ON (a.system_id, a.hour)",
+        "CREATE INDEX kill_system_time IF NOT EXISTS FOR (k:Kill) ON (k.system_id, k.time)",
+        "CREATE INDEX kill_time IF NOT EXISTS FOR (k:Kill) ON (k.time)",
+        "CREATE INDEX kill_id IF NOT EXISTS FOR (k:Kill) ON (k.killmail_id)",
+    ];
+    for create_index_statement in create_index_statements {
+        graph.run(query(create_index_statement)).await?;
+    }
+    Ok(())
+}
+
+pub async fn save_activity_samples(
     graph: Arc<Graph>,
-    source_system: i64,
-    dest_system: i64,
-) -> Result<bool, Error> {
-    let jump_exists_statement = "\
-        MATCH (:System {system_id: $source_system})-[r:JUMP]->(:System {system_id: $dest_system})
-        RETURN COUNT(r) AS count";
+    samples: &[ActivitySample],
+) -> Result<(), Error> {
+    let save_samples_statement = "\
+        UNWIND $samples AS sample
+        MERGE (a:ActivitySample {system_id: sample.system_id, hour: sample.hour})
+        SET a.kills = sample.kills, a.pod_kills = sample.pod_kills, a.npc_kills = sample.npc_kills,
+            a.jumps = sample.jumps";
+    let samples: Vec<HashMap<String, i64>> = samples
+        .iter()
+        .map(|sample| {
+            HashMap::from([
+                (String::from("system_id"), sample.system_id),
+                (String::from("hour"), sample.hour),
+                (String::from("kills"), i64::from(sample.kills)),
+                (String::from("pod_kills"), i64::from(sample.pod_kills)),
+                (String::from("npc_kills"), i64::from(sample.npc_kills)),
+                (String::from("jumps"), i64::from(sample.jumps)),
+            ])
+        })
+        .collect();
+    graph
+        .run(query(save_samples_statement).param("samples", samples))
+        .await?;
+    Ok(())
+}
+
+/// Deletes the activity samples of hours that started before `before`.
+pub async fn prune_activity_samples(graph: Arc<Graph>, before: i64) -> Result<(), Error> {
+    let prune_samples_statement = "\
+        MATCH (a:ActivitySample)
+        WHERE a.hour < $before
+        DELETE a";
+    graph
+        .run(query(prune_samples_statement).param("before", before))
+        .await?;
+    Ok(())
+}
+
+fn activity_sample_from_row(row: &Row) -> Result<ActivitySample, Error> {
+    Ok(ActivitySample {
+        system_id: row.get("system_id")?,
+        hour: row.get("hour")?,
+        kills: row.get("kills")?,
+        pod_kills: row.get("pod_kills")?,
+        npc_kills: row.get("npc_kills")?,
+        jumps: row.get("jumps")?,
+    })
+}
+
+/// Gets the activity samples of the given hour of the day (UTC), across all the history there is.
+pub async fn get_hour_of_day_activity_samples(
+    graph: Arc<Graph>,
+    hour_of_day: u32,
+) -> Result<Vec<ActivitySample>, Error> {
+    let get_samples_statement = "\
+        MATCH (a:ActivitySample)
+        WHERE (a.hour % 86400) / 3600 = $hour_of_day
+        RETURN a.system_id AS system_id, a.hour AS hour, a.kills AS kills,
+               coalesce(a.pod_kills, 0) AS pod_kills, coalesce(a.npc_kills, 0) AS npc_kills,
+               a.jumps AS jumps";
+    let mut result = graph
+        .execute(query(get_samples_statement).param("hour_of_day", i64::from(hour_of_day)))
+        .await?;
+    let mut samples = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        samples.push(activity_sample_from_row(&row)?);
+    }
+
+    Ok(samples)
+}
+
+/// Gets the activity samples of every hour that started at or after `since`.
+pub async fn get_activity_samples(
+    graph: Arc<Graph>,
+    since: i64,
+) -> Result<Vec<ActivitySample>, Error> {
+    let get_samples_statement = "\
+        MATCH (a:ActivitySample)
+        WHERE a.hour >= $since
+        RETURN a.system_id AS system_id, a.hour AS hour, a.kills AS kills,
+               coalesce(a.pod_kills, 0) AS pod_kills, coalesce(a.npc_kills, 0) AS npc_kills,
+               a.jumps AS jumps";
+    let mut result = graph
+        .execute(query(get_samples_statement).param("since", since))
+        .await?;
+    let mut samples = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        samples.push(activity_sample_from_row(&row)?);
+    }
+
+    Ok(samples)
+}
+
+/// Gets one system's activity samples of every hour that started at or after `since`.
+pub async fn get_system_activity_samples(
+    graph: Arc<Graph>,
+    system_id: i64,
+    since: i64,
+) -> Result<Vec<ActivitySample>, Error> {
+    let get_samples_statement = "\
+        MATCH (a:ActivitySample {system_id: $system_id})
+        WHERE a.hour >= $since
+        RETURN a.system_id AS system_id, a.hour AS hour, a.kills AS kills,
+               coalesce(a.pod_kills, 0) AS pod_kills, coalesce(a.npc_kills, 0) AS npc_kills,
+               a.jumps AS jumps";
     let mut result = graph
         .execute(
-            query(jump_exists_statement)
-                .param("source_system", source_system)
-                .param("dest_system", dest_system),
+            query(get_samples_statement)
+                .param("system_id", system_id)
+                .param("since", since),
         )
         .await?;
+    let mut samples = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        samples.push(activity_sample_from_row(&row)?);
+    }
+
+    Ok(samples)
+}
+
+/// Remembers the galaxy-wide risk of a jump from the last risk refresh, so a single system's risk
+/// can be updated between refreshes.
+pub async fn set_baseline_jump_risk(graph: Arc<Graph>, risk: f64) -> Result<(), Error> {
+    let set_baseline_statement = "\
+        MERGE (g:Galaxy)
+        SET g.baseline_jump_risk = $risk";
+    graph
+        .run(query(set_baseline_statement).param("risk", risk))
+        .await?;
+    Ok(())
+}
+
+pub async fn get_baseline_jump_risk(graph: Arc<Graph>) -> Result<Option<f64>, Error> {
+    let get_baseline_statement = "MATCH (g:Galaxy) RETURN g.baseline_jump_risk AS risk LIMIT 1";
+    let mut result = graph.execute(query(get_baseline_statement)).await?;
+
     match result.next().await? {
-        Some(row) => Ok(row_count_is_positive(row)),
+        Some(row) => Ok(row.get("risk")?),
+        None => Ok(None),
+    }
+}
+
+/// A single kill reported by the live kill feed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kill {
+    pub killmail_id: i64,
+    pub system_id: i64,
+    /// When the kill happened, in seconds since the epoch.
+    pub time: i64,
+    pub ship_type_id: i64,
+    pub pod_kill: bool,
+    pub attacker_count: u32,
+    /// Where the victim died, when the killmail says.
+    pub position: Option<(f64, f64, f64)>,
+}
+
+pub async fn save_kill(graph: Arc<Graph>, kill: &Kill) -> Result<(), Error> {
+    let save_kill_statement = "\
+        MERGE (k:Kill {killmail_id: $killmail_id})
+        SET k.system_id = $system_id,
+            k.time = $time,
+            k.ship_type_id = $ship_type_id,
+            k.pod_kill = $pod_kill,
+            k.attacker_count = $attacker_count,
+            k.x = $x,
+            k.y = $y,
+            k.z = $z";
+    let (x, y, z) = match kill.position {
+        Some((x, y, z)) => (Some(x), Some(y), Some(z)),
+        None => (None, None, None),
+    };
+
+    graph
+        .run(
+            query(save_kill_statement)
+                .param("killmail_id", kill.killmail_id)
+                .param("system_id", kill.system_id)
+                .param("time", kill.time)
+                .param("ship_type_id", kill.ship_type_id)
+                .param("pod_kill", kill.pod_kill)
+                .param("attacker_count", i64::from(kill.attacker_count))
+                .param("x", x)
+                .param("y", y)
+                .param("z", z),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Gets the kills the live kill feed reported since `since`.
+pub async fn get_kills_since(graph: Arc<Graph>, since: i64) -> Result<Vec<Kill>, Error> {
+    let get_kills_statement = "\
+        MATCH (k:Kill)
+        WHERE k.time >= $since
+        RETURN k.killmail_id AS killmail_id, k.system_id AS system_id, k.time AS time,
+               k.ship_type_id AS ship_type_id, k.pod_kill AS pod_kill,
+               k.attacker_count AS attacker_count, k.x AS x, k.y AS y, k.z AS z";
+    let mut result = graph
+        .execute(query(get_kills_statement).param("since", since))
+        .await?;
+    let mut kills = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        let position = match (
+            row.get::<Option<f64>>("x")?,
+            row.get::<Option<f64>>("y")?,
+            row.get::<Option<f64>>("z")?,
+        ) {
+            (Some(x), Some(y), Some(z)) => Some((x, y, z)),
+            _ => None,
+        };
+        kills.push(Kill {
+            killmail_id: row.get("killmail_id")?,
+            system_id: row.get("system_id")?,
+            time: row.get("time")?,
+            ship_type_id: row.get("ship_type_id")?,
+            pod_kill: row.get("pod_kill")?,
+            attacker_count: row.get("attacker_count")?,
+            position,
+        });
+    }
+
+    Ok(kills)
+}
+
+/// Gets the risk of jumping into every system under a risk model, as of the last refresh.
+pub async fn get_system_risks(
+    graph: Arc<Graph>,
+    risk_model: RiskModelKind,
+) -> Result<HashMap<i64, f64>, Error> {
+    let get_risks_statement = "\
+        MATCH (:System)-[r:JUMP|BRIDGE|WORMHOLE]->(s:System)
+        RETURN s.system_id AS system_id, max(coalesce(r[$property], r.risk, 0.0)) AS risk";
+    let mut result = graph
+        .execute(query(get_risks_statement).param("property", risk_model.property()))
+        .await?;
+    let mut risks = HashMap::new();
+
+    while let Some(row) = result.next().await? {
+        risks.insert(row.get("system_id")?, row.get("risk")?);
+    }
+
+    Ok(risks)
+}
+
+/// Gets the risk of each jump of a route under a risk model, in order. Where two systems are
+/// connected in more than one way, the least risky connection counts. Fails if two systems of the
+/// route aren't connected at all.
+pub async fn get_route_hop_risks(
+    graph: Arc<Graph>,
+    system_names: Vec<String>,
+    risk_model: RiskModelKind,
+) -> Result<Vec<f64>, Error> {
+    let hop_risks_statement = "
+        UNWIND range(0, size($system_names) - 2) AS hop
+        OPTIONAL MATCH (:System {name: $system_names[hop]})-[r:JUMP|BRIDGE|WORMHOLE]->(:System {name: $system_names[hop + 1]})
+        RETURN hop, min(CASE WHEN r IS NOT NULL THEN coalesce(r[$property], r.risk, 0.0) END) AS risk
+        ORDER BY hop";
+    let mut result = graph
+        .execute(
+            query(hop_risks_statement)
+                .param("system_names", system_names.clone())
+                .param("property", risk_model.property()),
+        )
+        .await?;
+    let mut risks = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        let hop: usize = row.get::<i64>("hop")? as usize;
+        match row.get::<Option<f64>>("risk")? {
+            Some(risk) => risks.push(risk),
+            None => {
+                return Err(Error::MissingConnection(
+                    system_names[hop].clone(),
+                    system_names[hop + 1].clone(),
+                ));
+            }
+        }
+    }
+
+    Ok(risks)
+}
+
+/// A route that's re-evaluated after every risk update, with alerts posted to a webhook when one
+/// of its systems gets riskier than the threshold or a safer route appears.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedRoute {
+    pub name: String,
+    pub from_system_name: String,
+    pub to_system_name: String,
+    pub threshold: f64,
+    /// Never listed back, since webhook URLs carry the secret needed to post to them.
+    #[serde(default, skip_serializing)]
+    pub webhook_url: Option<String>,
+    #[serde(default)]
+    pub webhook_format: WebhookFormat,
+    /// The route being watched, empty until the saved route is first evaluated.
+    #[serde(default)]
+    pub route: Vec<String>,
+    /// The systems on the route that were over the threshold when last evaluated.
+    #[serde(default)]
+    pub hot_systems: Vec<String>,
+}
+
+/// Saves a route, replacing any saved route with the same name. The watched route is cleared, so
+/// it's found again on the next evaluation.
+pub async fn save_saved_route(graph: Arc<Graph>, saved_route: &SavedRoute) -> Result<(), Error> {
+    let save_route_statement = "\
+        MERGE (r:SavedRoute {name: $name})
+        SET r.from_system_name = $from_system_name,
+            r.to_system_name = $to_system_name,
+            r.threshold = $threshold,
+            r.webhook_url = $webhook_url,
+            r.webhook_format = $webhook_format,
+            r.route = [],
+            r.hot_systems = []";
+    graph
+        .run(
+            query(save_route_statement)
+                .param("name", saved_route.name.clone())
+                .param("from_system_name", saved_route.from_system_name.clone())
+                .param("to_system_name", saved_route.to_system_name.clone())
+                .param("threshold", saved_route.threshold)
+                .param("webhook_url", saved_route.webhook_url.clone())
+                .param("webhook_format", saved_route.webhook_format.as_str()),
+        )
+        .await?;
+    Ok(())
+}
+
+pub async fn get_saved_routes(graph: Arc<Graph>) -> Result<Vec<SavedRoute>, Error> {
+    let get_routes_statement = "\
+        MATCH (r:SavedRoute)
+        RETURN r.name AS name, r.from_system_name AS from_system_name,
+               r.to_system_name AS to_system_name, r.threshold AS threshold,
+               r.webhook_url AS webhook_url, r.webhook_format AS webhook_format,
+               r.route AS route, r.hot_systems AS hot_systems
+        ORDER BY name";
+    let mut result = graph.execute(query(get_routes_statement)).await?;
+    let mut saved_routes = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        saved_routes.push(SavedRoute {
+            name: row.get("name")?,
+            from_system_name: row.get("from_system_name")?,
+            to_system_name: row.get("to_system_name")?,
+            threshold: row.get("threshold")?,
+            webhook_url: row.get("webhook_url")?,
+            webhook_format: WebhookFormat::parse(&row.get::<String>("webhook_format")?),
+            route: row.get("route")?,
+            hot_systems: row.get("hot_systems")?,
+        });
+    }
+
+    Ok(saved_routes)
+}
+
+/// Removes a saved route, returning whether there was one with the name.
+pub async fn remove_saved_route(graph: Arc<Graph>, name: String) -> Result<bool, Error> {
+    let remove_route_statement = "\
+        MATCH (r:SavedRoute {name: $name})
+        DELETE r
+        RETURN count(*) AS removed";
+    let mut result = graph
+        .execute(query(remove_route_statement).param("name", name))
+        .await?;
+
+    match result.next().await? {
+        Some(row) => Ok(row.get::<i64>("removed")? > 0),
         None => Ok(false),
     }
 }
 
-async fn create_system_jump_if_missing(
+/// Remembers the route a saved route watches and which of its systems are hot.
+pub async fn set_saved_route_state(
     graph: Arc<Graph>,
-    source_system: i64,
-    dest_system: i64,
+    name: &str,
+    route: Vec<String>,
+    hot_systems: Vec<String>,
 ) -> Result<(), Error> {
-    if !jump_exists(graph.clone(), source_system, dest_system).await? {
-        create_system_jump(graph, source_system, dest_system).await?;
+    let set_state_statement = "\
+        MATCH (r:SavedRoute {name: $name})
+        SET r.route = $route, r.hot_systems = $hot_systems";
+    graph
+        .run(
+            query(set_state_statement)
+                .param("name", name)
+                .param("route", route)
+                .param("hot_systems", hot_systems),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Counts the ship and pod kills the live kill feed reported in a system since `since`.
+pub async fn get_recent_kill_counts(
+    graph: Arc<Graph>,
+    system_id: i64,
+    since: i64,
+) -> Result<(u32, u32), Error> {
+    let count_kills_statement = "\
+        MATCH (k:Kill {system_id: $system_id})
+        WHERE k.time >= $since
+        RETURN count(CASE WHEN NOT k.pod_kill THEN 1 END) AS ship_kills,
+               count(CASE WHEN k.pod_kill THEN 1 END) AS pod_kills";
+    let mut result = graph
+        .execute(
+            query(count_kills_statement)
+                .param("system_id", system_id)
+                .param("since", since),
+        )
+        .await?;
+
+    match result.next().await? {
+        Some(row) => Ok((row.get("ship_kills")?, row.get("pod_kills")?)),
+        None => Ok((0, 0)),
     }
+}
+
+/// Deletes the kills that happened before `before`.
+pub async fn prune_kills(graph: Arc<Graph>, before: i64) -> Result<(), Error> {
+    let prune_kills_statement = "\
+        MATCH (k:Kill)
+        WHERE k.time < $before
+        DELETE k";
+    graph
+        .run(query(prune_kills_statement).param("before", before))
+        .await?;
+    Ok(())
+}
+
+/// Creates the `JUMP` relationship for a stargate, which records the gate pair it goes through.
+/// Any jump between the two systems that doesn't record its stargate is deleted first, so it isn't
+/// left alongside the new one. The gate type is set by `set_stargate_gate_types`.
+async fn create_stargate_jump(graph: Arc<Graph>, stargate: &Stargate) -> Result<(), Error> {
+    let create_jump_statement = "\
+        MATCH (source:System {system_id: $system_id})
+        MATCH (dest:System {system_id: $destination_system_id})
+        OPTIONAL MATCH (source)-[untyped:JUMP]->(dest)
+        WHERE untyped.stargate_id IS NULL
+        DELETE untyped
+        WITH DISTINCT source, dest
+        MERGE (source)-[r:JUMP {stargate_id: $stargate_id}]->(dest)
+        ON CREATE SET r.cost = 1
+        SET r.destination_stargate_id = $destination_stargate_id";
+
+    graph
+        .run(
+            query(create_jump_statement)
+                .param("system_id", stargate.system_id)
+                .param("destination_system_id", stargate.destination_system_id)
+                .param("stargate_id", stargate.stargate_id)
+                .param("destination_stargate_id", stargate.destination_stargate_id),
+        )
+        .await?;
+    Ok(())
+}
 
+/// Links `JUMP` relationships to the stargates they go through. Jumps saved before they recorded
+/// their stargate are matched up by the systems they connect.
+pub async fn link_stargate_jumps(graph: Arc<Graph>) -> Result<(), Error> {
+    let link_jumps_statement = "\
+        MATCH (sg:Stargate)
+        MATCH (:System {system_id: sg.system_id})-[r:JUMP]->(:System {system_id: sg.destination_system_id})
+        WHERE r.stargate_id IS NULL OR r.stargate_id = sg.stargate_id
+        SET r.stargate_id = sg.stargate_id,
+            r.destination_stargate_id = sg.destination_stargate_id";
+
+    graph.run(query(link_jumps_statement)).await?;
     Ok(())
 }
 
-pub async fn create_system_jump(
+/// The distinct type IDs of the saved stargates.
+pub async fn get_stargate_type_ids(graph: Arc<Graph>) -> Result<Vec<i64>, Error> {
+    let get_type_ids_statement = "MATCH (sg:Stargate) RETURN DISTINCT sg.type_id AS type_id";
+    let mut result = graph.execute(query(get_type_ids_statement)).await?;
+    let mut type_ids = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        if let Ok(type_id) = row.get("type_id") {
+            type_ids.push(type_id);
+        }
+    }
+
+    Ok(type_ids)
+}
+
+/// Sets the gate type of the jumps through stargates of the given types.
+pub async fn set_stargate_gate_types(
     graph: Arc<Graph>,
-    source_system: i64,
-    dest_system: i64,
+    gate_type: GateType,
+    type_ids: Vec<i64>,
 ) -> Result<(), Error> {
-    let inbound_connection = "\
-        MATCH (source:System {system_id: $source_system_id})
-        MATCH (dest:System {system_id: $dest_system_id})
-        CREATE (source)-[:JUMP {cost: 1}]->(dest)";
+    let set_gate_types_statement = "\
+        MATCH (sg:Stargate)
+        WHERE sg.type_id IN $type_ids
+        MATCH (:System {system_id: sg.system_id})-[r:JUMP {stargate_id: sg.stargate_id}]->()
+        SET r.gate_type = $gate_type";
 
     graph
         .run(
-            query(inbound_connection)
-                .param("source_system_id", source_system)
-                .param("dest_system_id", dest_system),
+            query(set_gate_types_statement)
+                .param("type_ids", type_ids)
+                .param("gate_type", gate_type.as_str()),
         )
         .await?;
     Ok(())
@@ -421,14 +1585,21 @@ pub async fn drop_jump_risk_graph(graph: &Arc<Graph>) -> Result<String, Error> {
 
 pub async fn build_system_jump_graph(graph: Arc<Graph>) -> Result<String, Error> {
     let build_graph = "\
-        CALL gds.graph.project(
+        MATCH (source:System)
+        OPTIONAL MATCH (source)-[r:JUMP|BRIDGE|WORMHOLE]->(target:System)
+        WITH gds.graph.project(
             'system-map',
-            'System',
-            'JUMP',
+            source,
+            target,
             {
-                relationshipProperties: 'cost'
+                relationshipType: CASE type(r)
+                    WHEN 'JUMP' THEN toUpper(coalesce(r.gate_type, 'system')) + '_GATE'
+                    ELSE type(r)
+                END,
+                relationshipProperties: r {.cost}
             }
-        )";
+        ) AS projection
+        RETURN projection.graphName AS graphName";
     let mut result = graph.execute(query(build_graph)).await?;
     let row = result
         .next()
@@ -439,14 +1610,25 @@ pub async fn build_system_jump_graph(graph: Arc<Graph>) -> Result<String, Error>
 
 pub async fn build_jump_risk_graph(graph: Arc<Graph>) -> Result<String, Error> {
     let build_graph = "\
-        CALL gds.graph.project(
+        MATCH (source:System)
+        OPTIONAL MATCH (source)-[r:JUMP|BRIDGE|WORMHOLE]->(target:System)
+        WITH gds.graph.project(
             'jump-risk',
-            'System',
-            'JUMP',
+            source,
+            target,
             {
-                relationshipProperties: 'risk'
+                relationshipType: CASE type(r)
+                    WHEN 'JUMP' THEN toUpper(coalesce(r.gate_type, 'system')) + '_GATE'
+                    ELSE type(r)
+                END,
+                relationshipProperties: {
+                    risk: coalesce(r.risk, 0.0),
+                    security_risk: coalesce(r.security_risk, r.risk, 0.0),
+                    pod_kill_risk: coalesce(r.pod_kill_risk, r.risk, 0.0)
+                }
             }
-        )";
+        ) AS projection
+        RETURN projection.graphName AS graphName";
     let mut result = graph.execute(query(build_graph)).await?;
     let row = result
         .next()
@@ -455,9 +1637,177 @@ pub async fn build_jump_risk_graph(graph: Arc<Graph>) -> Result<String, Error> {
     Ok(row.get("graphName")?)
 }
 
-pub async fn drop_system_connections(graph: &Arc<Graph>, system_name: &str) -> Result<(), Error> {
+/// Lists the relationship types that made it into a projection. Types with no relationships in
+/// the database are left out of a projection, and GDS rejects a filter on a type it doesn't have.
+async fn get_projected_relationship_types(
+    graph: &Arc<Graph>,
+    graph_name: &str,
+) -> Result<Vec<String>, Error> {
+    let list_relationship_types = "\
+        CALL gds.graph.list($graph_name)
+        YIELD schema
+        RETURN keys(schema.relationships) AS relationshipTypes";
+    let mut result = graph
+        .execute(query(list_relationship_types).param("graph_name", graph_name))
+        .await?;
+
+    match result.next().await? {
+        Some(row) => Ok(row.get("relationshipTypes")?),
+        None => Err(Error::ProjectionMissing(graph_name.to_string())),
+    }
+}
+
+/// The largest ship a wormhole lets through, as EVE Scout reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShipSize {
+    Small,
+    Medium,
+    Large,
+    Xlarge,
+    Capital,
+}
+
+impl ShipSize {
+    pub const ALL: [ShipSize; 5] = [
+        ShipSize::Small,
+        ShipSize::Medium,
+        ShipSize::Large,
+        ShipSize::Xlarge,
+        ShipSize::Capital,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShipSize::Small => "small",
+            ShipSize::Medium => "medium",
+            ShipSize::Large => "large",
+            ShipSize::Xlarge => "xlarge",
+            ShipSize::Capital => "capital",
+        }
+    }
+
+    /// The wormhole sizes a ship of this size fits through.
+    pub fn fits_through(&self) -> Vec<String> {
+        ShipSize::ALL
+            .iter()
+            .filter(|size| *size >= self)
+            .map(|size| size.as_str().to_string())
+            .collect()
+    }
+}
+
+/// Options that change which connections a route is allowed to use.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RouteOptions {
+    /// Use registered Ansiblex jump bridges.
+    #[serde(default)]
+    pub bridges: bool,
+    /// Use the wormholes reported by EVE Scout.
+    #[serde(default = "default_true")]
+    pub wormholes: bool,
+    /// Stargates of these types won't be used, given as a comma separated list.
+    #[serde(default, deserialize_with = "comma_separated")]
+    pub avoid_gates: Vec<GateType>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn comma_separated<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    let text = String::deserialize(deserializer)?;
+    text.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            T::deserialize(serde::de::value::StrDeserializer::<serde::de::value::Error>::new(item))
+                .map_err(serde::de::Error::custom)
+        })
+        .collect()
+}
+
+impl Default for RouteOptions {
+    fn default() -> Self {
+        Self {
+            bridges: false,
+            wormholes: true,
+            avoid_gates: Vec::new(),
+        }
+    }
+}
+
+impl RouteOptions {
+    /// The relationship types a route can use in the route projections, where stargate jumps are
+    /// split up by gate type.
+    fn projected_relationship_types(&self) -> Vec<&'static str> {
+        let mut types: Vec<&'static str> =
+            [GateType::System, GateType::Constellation, GateType::Region]
+                .iter()
+                .filter(|gate_type| !self.avoid_gates.contains(gate_type))
+                .map(GateType::projected_relationship_type)
+                .collect();
+        if self.bridges {
+            types.push("BRIDGE");
+        }
+        if self.wormholes {
+            types.push("WORMHOLE");
+        }
+        types
+    }
+
+    /// The relationship types a route can use in the database.
+    fn relationship_types(&self) -> Vec<String> {
+        let mut types = vec![String::from("JUMP")];
+        if self.bridges {
+            types.push(String::from("BRIDGE"));
+        }
+        if self.wormholes {
+            types.push(String::from("WORMHOLE"));
+        }
+        types
+    }
+
+    fn avoided_gate_types(&self) -> Vec<String> {
+        self.avoid_gates
+            .iter()
+            .map(|gate_type| gate_type.as_str().to_string())
+            .collect()
+    }
+}
+
+async fn route_relationship_types(
+    graph: &Arc<Graph>,
+    graph_name: &str,
+    options: &RouteOptions,
+) -> Result<Vec<String>, Error> {
+    let projected = get_projected_relationship_types(graph, graph_name).await?;
+    let mut types: Vec<String> = options
+        .projected_relationship_types()
+        .into_iter()
+        .filter(|t| projected.iter().any(|p| p == t))
+        .map(String::from)
+        .collect();
+    // Projections built before stargates were split up by gate type only have plain jumps.
+    if options.avoid_gates.is_empty() && projected.iter().any(|p| p == "JUMP") {
+        types.push(String::from("JUMP"));
+    }
+    Ok(types)
+}
+
+/// Drops the wormholes to and from a system. Wormholes used to be saved as plain `JUMP`s, so jumps
+/// with no stargate behind them are dropped too.
+pub async fn drop_system_wormholes(graph: &Arc<Graph>, system_name: &str) -> Result<(), Error> {
     let drop_thera_connections = "\
-        MATCH (:System {name: $system_name})-[r]-()
+        MATCH (system:System {name: $system_name})-[r:WORMHOLE|JUMP]-(other:System)
+        WHERE type(r) = 'WORMHOLE'
+            OR (r.stargate_id IS NULL
+                AND NOT EXISTS { (:Stargate {system_id: system.system_id, destination_system_id: other.system_id}) }
+                AND NOT EXISTS { (:Stargate {system_id: other.system_id, destination_system_id: system.system_id}) })
         DELETE r";
     graph
         .run(query(drop_thera_connections).param("system_name", system_name))
@@ -485,12 +1835,15 @@ pub async fn find_shortest_route(
     graph: Arc<Graph>,
     from_system_name: String,
     to_system_name: String,
+    options: &RouteOptions,
 ) -> Result<Option<Vec<String>>, Error> {
+    let relationship_types = route_relationship_types(&graph, "system-map", options).await?;
     let shortest_path_query = "\
         MATCH (source:System {name: $from_system_name}), (target:System {name: $to_system_name})
         CALL gds.shortestPath.dijkstra.stream('system-map', {
             sourceNode: source,
             targetNode: target,
+            relationshipTypes: $relationship_types,
             relationshipWeightProperty: 'cost'
         })
         YIELD index, sourceNode, targetNode, totalCost, nodeIds, costs, path
@@ -502,7 +1855,8 @@ pub async fn find_shortest_route(
         .execute(
             query(shortest_path_query)
                 .param("from_system_name", from_system_name)
-                .param("to_system_name", to_system_name),
+                .param("to_system_name", to_system_name)
+                .param("relationship_types", relationship_types),
         )
         .await?;
 
@@ -512,17 +1866,22 @@ pub async fn find_shortest_route(
     }
 }
 
+/// Finds the route with the least risk, as measured by the given risk model.
 pub async fn find_safest_route(
     graph: Arc<Graph>,
     from_system_name: String,
     to_system_name: String,
+    options: &RouteOptions,
+    risk_model: RiskModelKind,
 ) -> Result<Option<Vec<String>>, Error> {
+    let relationship_types = route_relationship_types(&graph, "jump-risk", options).await?;
     let shortest_path_query = "\
         MATCH (source:System {name: $from_system_name}), (target:System {name: $to_system_name})
         CALL gds.shortestPath.dijkstra.stream('jump-risk', {
             sourceNode: source,
             targetNode: target,
-            relationshipWeightProperty: 'risk'
+            relationshipTypes: $relationship_types,
+            relationshipWeightProperty: $weight_property
         })
         YIELD index, sourceNode, targetNode, totalCost, nodeIds, costs, path
         RETURN
@@ -533,7 +1892,9 @@ pub async fn find_safest_route(
         .execute(
             query(shortest_path_query)
                 .param("from_system_name", from_system_name)
-                .param("to_system_name", to_system_name),
+                .param("to_system_name", to_system_name)
+                .param("relationship_types", relationship_types)
+                .param("weight_property", risk_model.property()),
         )
         .await?;
 
@@ -543,6 +1904,252 @@ pub async fn find_safest_route(
     }
 }
 
+/// Finds every system within a number of jumps of the given system, along with how many jumps
+/// away it is. The system itself is included at zero jumps, so an empty result means it's unknown.
+pub async fn find_systems_within_jumps(
+    graph: Arc<Graph>,
+    system_name: String,
+    max_jumps: u32,
+    options: &RouteOptions,
+) -> Result<Vec<(System, u32)>, Error> {
+    let relationship_types = route_relationship_types(&graph, "system-map", options).await?;
+    let within_jumps_query = "\
+        MATCH (source:System {name: $system_name})
+        CALL gds.allShortestPaths.dijkstra.stream('system-map', {
+            sourceNode: source,
+            relationshipTypes: $relationship_types,
+            relationshipWeightProperty: 'cost'
+        })
+        YIELD targetNode, totalCost
+        WHERE totalCost <= $max_jumps
+        RETURN gds.util.asNode(targetNode) AS system, toInteger(totalCost) AS jumps
+        ORDER BY jumps
+    ";
+
+    let mut result = graph
+        .execute(
+            query(within_jumps_query)
+                .param("system_name", system_name)
+                .param("relationship_types", relationship_types)
+                .param("max_jumps", max_jumps),
+        )
+        .await?;
+    let mut systems = Vec::new();
+
+    while let Some(row) = result.next().await? {
+        if let (Ok(system), Ok(jumps)) = (row.get::<System>("system"), row.get::<i64>("jumps")) {
+            systems.push((system, jumps as u32));
+        }
+    }
+
+    Ok(systems)
+}
+
+/// What a system has to look like to be the answer to a nearest system search. Every criterion
+/// that is set has to match.
+#[derive(Debug, Clone, Default)]
+pub struct SystemCriteria {
+    pub security: Option<SecurityBand>,
+    pub max_kills: Option<u32>,
+    pub names: Option<Vec<String>>,
+    /// The name of a system, such as Thera, that an EVE Scout wormhole has to connect to.
+    pub wormhole_to: Option<String>,
+}
+
+impl SystemCriteria {
+    fn security_param(&self) -> Option<String> {
+        self.security.map(|security| match security {
+            SecurityBand::High => String::from("high"),
+            SecurityBand::Low => String::from("low"),
+            SecurityBand::Null => String::from("null"),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct NearestSystem {
+    pub name: String,
+    pub jumps: u32,
+    pub route: Vec<String>,
+}
+
+/// Finds the closest system, other than the starting one, that matches the criteria, along with
+/// the route to it.
+pub async fn find_nearest_system(
+    graph: Arc<Graph>,
+    system_name: String,
+    criteria: &SystemCriteria,
+    options: &RouteOptions,
+) -> Result<Option<NearestSystem>, Error> {
+    let relationship_types = route_relationship_types(&graph, "system-map", options).await?;
+    let nearest_system_query = "\
+        MATCH (source:System {name: $system_name})
+        CALL gds.allShortestPaths.dijkstra.stream('system-map', {
+            sourceNode: source,
+            relationshipTypes: $relationship_types,
+            relationshipWeightProperty: 'cost'
+        })
+        YIELD targetNode, totalCost, nodeIds
+        WITH source, gds.util.asNode(targetNode) AS target, totalCost, nodeIds
+        WHERE target <> source
+            AND ($security IS NULL OR $security = CASE
+                WHEN target.security_status >= $high_sec_min_status THEN 'high'
+                WHEN target.security_status > $low_sec_above_status THEN 'low'
+                ELSE 'null'
+            END)
+            AND ($max_kills IS NULL OR target.kills <= $max_kills)
+            AND ($names IS NULL OR target.name IN $names)
+            AND ($wormhole_to IS NULL
+                OR EXISTS { (target)-[:WORMHOLE]-(:System {name: $wormhole_to}) })
+        RETURN
+            target.name AS name,
+            toInteger(totalCost) AS jumps,
+            [nodeId IN nodeIds | gds.util.asNode(nodeId).name] AS route
+        ORDER BY totalCost
+        LIMIT 1
+    ";
+
+    let mut result = graph
+        .execute(
+            query(nearest_system_query)
+                .param("system_name", system_name)
+                .param("relationship_types", relationship_types)
+                .param("security", criteria.security_param())
+                .param("high_sec_min_status", HIGH_SEC_MIN_STATUS)
+                .param("low_sec_above_status", LOW_SEC_ABOVE_STATUS)
+                .param("max_kills", criteria.max_kills)
+                .param("names", criteria.names.clone())
+                .param("wormhole_to", criteria.wormhole_to.clone()),
+        )
+        .await?;
+
+    match result.next().await? {
+        Some(row) => Ok(Some(NearestSystem {
+            name: row.get("name")?,
+            jumps: row.get::<i64>("jumps")? as u32,
+            route: row.get("route")?,
+        })),
+        None => Ok(None),
+    }
+}
+
+/// The weights a route can be measured by, each backed by its own projection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteWeight {
+    Jumps,
+    /// The risk of every jump, as the given model prices it.
+    Risk(RiskModelKind),
+}
+
+impl RouteWeight {
+    /// The projection to search and the relationship property to weigh it by.
+    fn projection(&self) -> (&'static str, &'static str) {
+        match self {
+            RouteWeight::Jumps => ("system-map", "cost"),
+            RouteWeight::Risk(risk_model) => ("jump-risk", risk_model.property()),
+        }
+    }
+}
+
+/// What a route minimizes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteMode {
+    /// The fewest jumps.
+    #[default]
+    Shortest,
+    /// The least risk.
+    Safest,
+}
+
+impl RouteMode {
+    pub const ALL: [RouteMode; 2] = [RouteMode::Shortest, RouteMode::Safest];
+
+    /// The weight to search by, with safest routes priced by the given risk model.
+    pub fn weight(&self, risk_model: RiskModelKind) -> RouteWeight {
+        match self {
+            RouteMode::Shortest => RouteWeight::Jumps,
+            RouteMode::Safest => RouteWeight::Risk(risk_model),
+        }
+    }
+}
+
+/// The best route to a destination and what it costs by the weight it was searched by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoundRoute {
+    pub cost: f64,
+    pub systems: Vec<String>,
+}
+
+/// Finds the best route from one system to each of the destinations with a single search.
+/// Destinations that can't be reached are left out of the result.
+pub async fn find_routes_from(
+    graph: Arc<Graph>,
+    from_system_name: String,
+    to_system_names: Vec<String>,
+    weight: RouteWeight,
+    options: &RouteOptions,
+) -> Result<HashMap<String, FoundRoute>, Error> {
+    let (graph_name, weight_property) = weight.projection();
+    let relationship_types = route_relationship_types(&graph, graph_name, options).await?;
+    let routes_query = "\
+        MATCH (source:System {name: $from_system_name})
+        CALL gds.allShortestPaths.dijkstra.stream($graph_name, {
+            sourceNode: source,
+            relationshipTypes: $relationship_types,
+            relationshipWeightProperty: $weight_property
+        })
+        YIELD targetNode, totalCost, nodeIds
+        WITH gds.util.asNode(targetNode) AS target, totalCost, nodeIds
+        WHERE target.name IN $to_system_names
+        RETURN
+            target.name AS name,
+            totalCost AS cost,
+            [nodeId IN nodeIds | gds.util.asNode(nodeId).name] AS nodeNames
+    ";
+
+    let mut result = graph
+        .execute(
+            query(routes_query)
+                .param("from_system_name", from_system_name)
+                .param("to_system_names", to_system_names)
+                .param("graph_name", graph_name)
+                .param("weight_property", weight_property)
+                .param("relationship_types", relationship_types),
+        )
+        .await?;
+    let mut routes = HashMap::new();
+
+    while let Some(row) = result.next().await? {
+        if let (Ok(name), Ok(cost), Ok(systems)) = (
+            row.get::<String>("name"),
+            row.get::<f64>("cost"),
+            row.get::<Vec<String>>("nodeNames"),
+        ) {
+            routes.insert(name, FoundRoute { cost, systems });
+        }
+    }
+
+    Ok(routes)
+}
+
+/// Measures the best route from one system to each of the destinations with a single search.
+/// Destinations that can't be reached are left out of the result.
+pub async fn get_route_costs(
+    graph: Arc<Graph>,
+    from_system_name: String,
+    to_system_names: Vec<String>,
+    weight: RouteWeight,
+    options: &RouteOptions,
+) -> Result<HashMap<String, f64>, Error> {
+    let routes =
+        find_routes_from(graph, from_system_name, to_system_names, weight, options).await?;
+    Ok(routes
+        .into_iter()
+        .map(|(name, route)| (name, route.cost))
+        .collect())
+}
+
 pub async fn remove_duplicate_systems(graph: Arc<Graph>) -> Result<(), Error> {
     let remove_duplicates = "
         MATCH (s:System)
@@ -617,29 +2224,111 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_calculate_total_risk_no_activity() {
-        // With no system activity, risk should just be the baseline.
-        let risk = calculate_total_risk(0, 0, 0.1);
-        assert_eq!(risk, 0.1);
+    fn test_risk_weight_searches_the_models_property() {
+        assert_eq!(
+            RouteWeight::Risk(RiskModelKind::SecurityWeighted).projection(),
+            ("jump-risk", "security_risk")
+        );
+        assert_eq!(
+            RouteWeight::Risk(RiskModelKind::KillsPerJump).projection(),
+            ("jump-risk", "risk")
+        );
+    }
+
+    #[test]
+    fn test_bridges_get_every_risk_models_property() {
+        let risks = bridge_risks("there", "b");
+        for kind in RiskModelKind::ALL {
+            let property = kind.property();
+            assert!(risks.contains(&format!(
+                "there.{property} = coalesce(head([(:System)-[j:JUMP]->(b) | j.{property}]), 0.0)"
+            )));
+        }
+    }
+
+    fn system(security_status: f64, region_name: &str, risk: f64) -> System {
+        System {
+            name: String::from("Jita"),
+            security_status,
+            system_id: 30000142,
+            region_id: Some(10000002),
+            region_name: Some(region_name.to_string()),
+            risk: Some(risk),
+            ..System::default()
+        }
+    }
+
+    #[test]
+    fn test_security_band_uses_rounded_security() {
+        assert_eq!(
+            system(0.46, "The Forge", 0.1).security_band(),
+            SecurityBand::High
+        );
+        assert_eq!(
+            system(0.44, "The Forge", 0.1).security_band(),
+            SecurityBand::Low
+        );
+        assert_eq!(
+            system(0.0, "The Forge", 0.1).security_band(),
+            SecurityBand::Null
+        );
     }
 
     #[test]
-    fn test_calculate_total_risk_with_kills_no_jumps() {
-        // With no jumps, risk is kills_squared + baseline.
-        let risk = calculate_total_risk(5, 0, 0.1);
-        assert_eq!(risk, 25.1);
+    fn test_system_filter_matches_all_criteria() {
+        let filter = SystemFilter {
+            security: Some(SecurityBand::High),
+            region: Some(String::from("the forge")),
+            max_risk: Some(0.5),
+        };
+
+        assert!(filter.matches(&system(0.9, "The Forge", 0.1)));
+        assert!(!filter.matches(&system(0.9, "Domain", 0.1)));
+        assert!(!filter.matches(&system(0.9, "The Forge", 0.6)));
+        assert!(!filter.matches(&system(0.2, "The Forge", 0.1)));
+        assert!(SystemFilter::default().matches(&system(0.2, "Domain", 9.0)));
     }
 
     #[test]
-    fn test_calculate_total_risk_with_jumps_no_kills() {
-        // With no kills, risk should just be the baseline.
-        let risk = calculate_total_risk(0, 100, 0.1);
-        assert_eq!(risk, 0.1);
+    fn test_route_options_split_stargates_by_gate_type() {
+        let options: RouteOptions =
+            serde_json::from_str(r#"{"bridges": true, "avoid_gates": "region, constellation"}"#)
+                .unwrap();
+
+        assert_eq!(
+            options.avoid_gates,
+            vec![GateType::Region, GateType::Constellation]
+        );
+        assert_eq!(
+            options.projected_relationship_types(),
+            vec!["SYSTEM_GATE", "BRIDGE", "WORMHOLE"]
+        );
+        assert_eq!(
+            options.relationship_types(),
+            vec!["JUMP", "BRIDGE", "WORMHOLE"]
+        );
+    }
+
+    #[test]
+    fn test_gate_type_comes_from_the_stargate_type_name() {
+        assert_eq!(
+            GateType::from_type_name("Stargate (Caldari Region)"),
+            Some(GateType::Region)
+        );
+        assert_eq!(
+            GateType::from_type_name("Stargate (Amarr Constellation)"),
+            Some(GateType::Constellation)
+        );
+        assert_eq!(
+            GateType::from_type_name("Stargate (Minmatar System)"),
+            Some(GateType::System)
+        );
+        assert_eq!(GateType::from_type_name("Stargate (Triglavian)"), None);
     }
+
     #[test]
-    fn test_calculate_total_risk_normal_activity() {
-        // (10^2 / 200) + 0.1 = 100 / 200 + 0.1 = 0.5 + 0.1 = 0.6
-        let risk = calculate_total_risk(10, 200, 0.1);
-        assert!((risk - 0.6).abs() < f64::EPSILON);
+    fn test_ships_fit_through_wormholes_their_size_or_larger() {
+        assert_eq!(ShipSize::Xlarge.fits_through(), vec!["xlarge", "capital"]);
+        assert_eq!(ShipSize::Small.fits_through().len(), 5);
     }
 }
@@ -0,0 +1,164 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// A cost that the search can order. Costs are compared as a whole, so a tuple breaks ties on its
+/// first element with the second.
+pub trait Cost: Copy {
+    fn compare(&self, other: &Self) -> Ordering;
+}
+
+impl Cost for f64 {
+    fn compare(&self, other: &Self) -> Ordering {
+        self.total_cmp(other)
+    }
+}
+
+impl Cost for (f64, f64) {
+    fn compare(&self, other: &Self) -> Ordering {
+        self.0
+            .total_cmp(&other.0)
+            .then_with(|| self.1.total_cmp(&other.1))
+    }
+}
+
+#[derive(Debug)]
+struct Candidate<C> {
+    cost: C,
+    node: usize,
+}
+
+impl<C: Cost> PartialEq for Candidate<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<C: Cost> Eq for Candidate<C> {}
+
+impl<C: Cost> Ord for Candidate<C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .compare(&self.cost)
+            .then_with(|| self.node.cmp(&other.node))
+    }
+}
+
+impl<C: Cost> PartialOrd for Candidate<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The cheapest path found to a target.
+#[derive(Debug)]
+pub struct Path<L, E> {
+    /// The label the target was reached with.
+    pub label: L,
+    /// Every step from the start to the target, as the node left, the edge taken and the node
+    /// reached.
+    pub steps: Vec<(usize, E, usize)>,
+}
+
+/// Finds the cheapest path from `start` to the first node for which `is_target` holds. Every node
+/// keeps the label it was reached with at the lowest cost, and `edges` gives the nodes reachable
+/// from a node along with the edge taken and the label they would be reached with. The cost of a
+/// label must never be lower than the cost of the label it was reached from.
+pub fn dijkstra<L, E, C>(
+    node_count: usize,
+    start: usize,
+    start_label: L,
+    is_target: impl Fn(usize) -> bool,
+    cost: impl Fn(&L) -> C,
+    mut edges: impl FnMut(usize, &L) -> Vec<(usize, E, L)>,
+) -> Option<Path<L, E>>
+where
+    L: Copy,
+    E: Copy,
+    C: Cost,
+{
+    let mut best: Vec<Option<L>> = vec![None; node_count];
+    let mut previous: Vec<Option<(usize, E)>> = vec![None; node_count];
+    best[start] = Some(start_label);
+    let mut queue = BinaryHeap::from([Candidate {
+        cost: cost(&start_label),
+        node: start,
+    }]);
+
+    while let Some(Candidate {
+        cost: queued_cost,
+        node,
+    }) = queue.pop()
+    {
+        let Some(label) = best[node] else { continue };
+        if cost(&label).compare(&queued_cost) == Ordering::Less {
+            continue;
+        }
+        if is_target(node) {
+            let mut steps = Vec::new();
+            let mut current = node;
+            while current != start {
+                let (from, edge) = previous[current]?;
+                steps.push((from, edge, current));
+                current = from;
+            }
+            steps.reverse();
+            return Some(Path { label, steps });
+        }
+
+        for (next, edge, next_label) in edges(node, &label) {
+            let next_cost = cost(&next_label);
+            if best[next].is_none_or(|b| next_cost.compare(&cost(&b)) == Ordering::Less) {
+                best[next] = Some(next_label);
+                previous[next] = Some((node, edge));
+                queue.push(Candidate {
+                    cost: next_cost,
+                    node: next,
+                });
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dijkstra_takes_the_cheaper_detour() {
+        // 0 -> 1 -> 3 costs 1 + 5, 0 -> 2 -> 3 costs 2 + 2.
+        let graph: [&[(usize, f64)]; 4] = [&[(1, 1.0), (2, 2.0)], &[(3, 5.0)], &[(3, 2.0)], &[]];
+        let path = dijkstra(
+            graph.len(),
+            0,
+            0.0,
+            |node| node == 3,
+            |&cost| cost,
+            |node, &cost| {
+                graph[node]
+                    .iter()
+                    .map(|&(next, weight)| (next, (), cost + weight))
+                    .collect()
+            },
+        )
+        .unwrap();
+        assert_eq!(path.label, 4.0);
+        let nodes: Vec<(usize, usize)> =
+            path.steps.iter().map(|&(from, _, to)| (from, to)).collect();
+        assert_eq!(nodes, vec![(0, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn test_dijkstra_gives_up_on_unreachable_targets() {
+        let path = dijkstra(
+            2,
+            0,
+            0.0,
+            |node| node == 1,
+            |&cost| cost,
+            |_, _| Vec::<(usize, (), f64)>::new(),
+        );
+        assert!(path.is_none());
+    }
+}
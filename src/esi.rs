@@ -82,9 +82,45 @@ pub async fn get_system_ids(client: &Client) -> Result<Vec<i64>, Error> {
     process_response(response).await
 }
 
+pub async fn get_region_ids(client: &Client) -> Result<Vec<i64>, Error> {
+    let regions_url = "https://esi.evetech.net/latest/universe/regions/";
+    let response = client.get(regions_url).send().await?;
+    process_response(response).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegionResponse {
+    pub constellations: Vec<i64>,
+    pub name: String,
+    pub region_id: i64,
+}
+
+pub async fn get_region_details(client: &Client, region_id: i64) -> Result<RegionResponse, Error> {
+    let region_url = format!("https://esi.evetech.net/latest/universe/regions/{region_id}");
+    let response = client.get(region_url).send().await?;
+    process_response(response).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NameResponse {
+    pub id: i64,
+    pub name: String,
+}
+
+/// Looks up the names of up to 1000 IDs of any kind in one request.
+pub async fn get_names(client: &Client, ids: &[i64]) -> Result<Vec<NameResponse>, Error> {
+    let names_url = "https://esi.evetech.net/latest/universe/names/";
+    let response = client.post(names_url).json(ids).send().await?;
+    process_response(response).await
+}
+
 #[derive(Debug, Deserialize)]
 pub struct SystemKills {
     pub ship_kills: i32,
+    #[serde(default)]
+    pub pod_kills: i32,
+    #[serde(default)]
+    pub npc_kills: i32,
     pub system_id: i64,
 }
 
@@ -27,7 +27,7 @@ pub struct EveScoutSignature {
     completed: bool,
     wh_exits_outward: bool,
     wh_type: String,
-    max_ship_size: String,
+    pub max_ship_size: String,
     expires_at: String,
     remaining_hours: i64,
     pub signature_type: String,
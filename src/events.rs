@@ -0,0 +1,171 @@
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// How many events a slow subscriber can fall behind by before it starts missing them.
+const EVENT_BUFFER: usize = 64;
+
+static EVENTS: LazyLock<broadcast::Sender<GraphEvent>> =
+    LazyLock::new(|| broadcast::channel(EVENT_BUFFER).0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncJob {
+    Systems,
+    Stargates,
+    Wormholes,
+}
+
+impl SyncJob {
+    pub const ALL: [SyncJob; 3] = [SyncJob::Systems, SyncJob::Stargates, SyncJob::Wormholes];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct WormholeConnection {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RiskChange {
+    pub system_id: i64,
+    pub name: String,
+    pub previous_risk: Option<f64>,
+    pub risk: f64,
+}
+
+impl RiskChange {
+    fn size(&self) -> f64 {
+        (self.risk - self.previous_risk.unwrap_or_default()).abs()
+    }
+}
+
+/// A change to the graph that clients may want to react to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GraphEvent {
+    WormholesChanged {
+        added: Vec<WormholeConnection>,
+        removed: Vec<WormholeConnection>,
+    },
+    RiskRefreshed {
+        most_changed: Vec<RiskChange>,
+    },
+    SyncFinished {
+        job: SyncJob,
+    },
+}
+
+impl GraphEvent {
+    /// The name the event is sent under.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GraphEvent::WormholesChanged { .. } => "wormholes_changed",
+            GraphEvent::RiskRefreshed { .. } => "risk_refreshed",
+            GraphEvent::SyncFinished { .. } => "sync_finished",
+        }
+    }
+}
+
+/// Sends an event to everyone subscribed. Events published while nobody is subscribed are
+/// dropped.
+pub fn publish(event: GraphEvent) {
+    let _ = EVENTS.send(event);
+}
+
+pub fn subscribe() -> broadcast::Receiver<GraphEvent> {
+    EVENTS.subscribe()
+}
+
+/// The wormhole connections that appeared and disappeared between two listings.
+pub fn wormhole_changes(
+    before: &[WormholeConnection],
+    after: &[WormholeConnection],
+) -> (Vec<WormholeConnection>, Vec<WormholeConnection>) {
+    let before_set: HashSet<&WormholeConnection> = before.iter().collect();
+    let after_set: HashSet<&WormholeConnection> = after.iter().collect();
+    let added = after
+        .iter()
+        .filter(|connection| !before_set.contains(connection))
+        .cloned()
+        .collect();
+    let removed = before
+        .iter()
+        .filter(|connection| !after_set.contains(connection))
+        .cloned()
+        .collect();
+    (added, removed)
+}
+
+/// The risk changes that moved the most, biggest first.
+pub fn most_changed(mut changes: Vec<RiskChange>, count: usize) -> Vec<RiskChange> {
+    changes.retain(|change| change.size() > 0.0);
+    changes.sort_by(|a, b| b.size().total_cmp(&a.size()));
+    changes.truncate(count);
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(from: &str, to: &str) -> WormholeConnection {
+        WormholeConnection {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    fn change(system_id: i64, previous_risk: Option<f64>, risk: f64) -> RiskChange {
+        RiskChange {
+            system_id,
+            name: system_id.to_string(),
+            previous_risk,
+            risk,
+        }
+    }
+
+    #[test]
+    fn test_wormhole_changes() {
+        let before = [connection("Thera", "Jita"), connection("Thera", "Amarr")];
+        let after = [connection("Thera", "Amarr"), connection("Turnur", "Rens")];
+
+        let (added, removed) = wormhole_changes(&before, &after);
+
+        assert_eq!(added, vec![connection("Turnur", "Rens")]);
+        assert_eq!(removed, vec![connection("Thera", "Jita")]);
+    }
+
+    #[test]
+    fn test_most_changed_orders_by_size_of_change() {
+        let changes = vec![
+            change(1, Some(0.1), 0.1),
+            change(2, Some(5.0), 0.5),
+            change(3, None, 1.0),
+            change(4, Some(0.2), 0.3),
+        ];
+
+        let most_changed = most_changed(changes, 2);
+
+        let ids: Vec<i64> = most_changed.iter().map(|change| change.system_id).collect();
+        assert_eq!(ids, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_published_events_reach_subscribers() {
+        let mut receiver = subscribe();
+
+        publish(GraphEvent::SyncFinished {
+            job: SyncJob::Stargates,
+        });
+
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            GraphEvent::SyncFinished {
+                job: SyncJob::Stargates
+            }
+        );
+    }
+}
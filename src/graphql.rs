@@ -0,0 +1,489 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Enum, Error, ErrorExtensions, Object, Result,
+    Schema, SimpleObject,
+};
+use neo4rs::Graph;
+
+use crate::database::{self, RouteOptions, Stargate, System};
+use crate::risk::{RiskModelKind, find_lowest_risk_route};
+use crate::search::{UnresolvedReason, resolve_system, search_systems};
+
+const DEFAULT_SYSTEMS_LIMIT: usize = 10;
+
+/// How deeply a query may nest, which leaves room for the introspection query GraphiQL sends.
+const MAX_QUERY_DEPTH: usize = 15;
+
+/// How many fields a query may resolve in total. Every level of `neighbors` can fan out to dozens
+/// of systems, so this keeps a query from walking large parts of the map.
+const MAX_QUERY_COMPLEXITY: usize = 500;
+
+pub type EveGraphSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// Builds the GraphQL schema, resolving against the given graph.
+pub fn schema(graph: Arc<Graph>) -> EveGraphSchema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(DataLoader::new(SystemLoader(graph.clone()), tokio::spawn))
+        .data(graph)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .limit_complexity(MAX_QUERY_COMPLEXITY)
+        .finish()
+}
+
+fn graph<'a>(ctx: &Context<'a>) -> Result<&'a Arc<Graph>> {
+    ctx.data::<Arc<Graph>>()
+}
+
+/// Looks up systems by ID, batching the lookups made while resolving one level of a query, such
+/// as the system of every neighbor, into a single query.
+pub struct SystemLoader(Arc<Graph>);
+
+impl Loader<i64> for SystemLoader {
+    type Value = System;
+    type Error = Arc<database::Error>;
+
+    async fn load(&self, keys: &[i64]) -> Result<HashMap<i64, System>, Self::Error> {
+        let systems = database::get_systems_by_id(self.0.clone(), keys.to_vec())
+            .await
+            .map_err(Arc::new)?;
+        Ok(systems
+            .into_iter()
+            .map(|system| (system.system_id, system))
+            .collect())
+    }
+}
+
+async fn load_system(ctx: &Context<'_>, system_id: i64) -> Result<Option<SystemNode>> {
+    let system = ctx
+        .data::<DataLoader<SystemLoader>>()?
+        .load_one(system_id)
+        .await?;
+    Ok(system.map(SystemNode))
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "crate::database::GateType")]
+pub enum GateType {
+    System,
+    Constellation,
+    Region,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "crate::database::ConnectionKind")]
+pub enum ConnectionKind {
+    Stargate,
+    Wormhole,
+    JumpBridge,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "crate::database::ShipSize")]
+pub enum ShipSize {
+    Small,
+    Medium,
+    Large,
+    Xlarge,
+    Capital,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "crate::risk::RiskModelKind")]
+pub enum RiskModel {
+    KillsPerJump,
+    SecurityWeighted,
+    PodKillWeighted,
+}
+
+#[derive(Enum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[graphql(remote = "crate::database::RouteMode")]
+pub enum RouteMode {
+    /// The fewest jumps.
+    #[default]
+    Shortest,
+    /// The least risk.
+    Safest,
+}
+
+#[derive(SimpleObject)]
+pub struct Constellation {
+    id: i64,
+    name: Option<String>,
+}
+
+#[derive(SimpleObject)]
+pub struct Region {
+    id: i64,
+    name: Option<String>,
+}
+
+pub struct SystemNode(System);
+
+#[Object(name = "System")]
+impl SystemNode {
+    async fn id(&self) -> i64 {
+        self.0.system_id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn security_status(&self) -> f64 {
+        self.0.security_status
+    }
+
+    async fn security_class(&self) -> &str {
+        &self.0.security_class
+    }
+
+    async fn constellation(&self) -> Constellation {
+        Constellation {
+            id: self.0.constellation_id,
+            name: self.0.constellation_name.clone(),
+        }
+    }
+
+    async fn region(&self) -> Option<Region> {
+        self.0.region_id.map(|id| Region {
+            id,
+            name: self.0.region_name.clone(),
+        })
+    }
+
+    /// Ship kills over the last hour.
+    async fn kills(&self) -> u32 {
+        self.0.kills
+    }
+
+    async fn pod_kills(&self) -> u32 {
+        self.0.pod_kills
+    }
+
+    async fn npc_kills(&self) -> u32 {
+        self.0.npc_kills
+    }
+
+    /// Jumps into the system over the last hour.
+    async fn jumps(&self) -> u32 {
+        self.0.jumps
+    }
+
+    /// The risk of jumping into the system, as of the last risk refresh.
+    async fn risk(&self) -> Option<f64> {
+        self.0.risk
+    }
+
+    /// The systems one stargate, jump bridge or wormhole jump away.
+    async fn neighbors(&self, ctx: &Context<'_>) -> Result<Vec<Neighbor>> {
+        let neighbors =
+            database::get_system_neighbors(graph(ctx)?.clone(), self.0.system_id).await?;
+        Ok(neighbors.into_iter().map(Neighbor).collect())
+    }
+
+    /// The wormholes out of the system.
+    async fn wormholes(&self, ctx: &Context<'_>) -> Result<Vec<Neighbor>> {
+        let neighbors =
+            database::get_system_neighbors(graph(ctx)?.clone(), self.0.system_id).await?;
+        Ok(neighbors
+            .into_iter()
+            .filter(|neighbor| neighbor.kind == database::ConnectionKind::Wormhole)
+            .map(Neighbor)
+            .collect())
+    }
+
+    async fn stargates(&self, ctx: &Context<'_>) -> Result<Vec<StargateNode>> {
+        let stargates =
+            database::get_system_stargates(graph(ctx)?.clone(), self.0.system_id).await?;
+        Ok(stargates.into_iter().map(StargateNode).collect())
+    }
+}
+
+pub struct Neighbor(database::Neighbor);
+
+/// A connection from one system to the next.
+#[Object]
+impl Neighbor {
+    async fn kind(&self) -> ConnectionKind {
+        self.0.kind.into()
+    }
+
+    async fn gate_type(&self) -> Option<GateType> {
+        self.0.gate_type.map(GateType::from)
+    }
+
+    async fn system_id(&self) -> i64 {
+        self.0.system_id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn system(&self, ctx: &Context<'_>) -> Result<Option<SystemNode>> {
+        load_system(ctx, self.0.system_id).await
+    }
+}
+
+pub struct StargateNode(Stargate);
+
+#[Object(name = "Stargate")]
+impl StargateNode {
+    async fn id(&self) -> i64 {
+        self.0.stargate_id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn destination_stargate_id(&self) -> i64 {
+        self.0.destination_stargate_id
+    }
+
+    async fn destination(&self, ctx: &Context<'_>) -> Result<Option<SystemNode>> {
+        load_system(ctx, self.0.destination_system_id).await
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Route {
+    mode: RouteMode,
+    systems: Vec<String>,
+    jumps: usize,
+    /// The summed risk of jumping into every system after the first.
+    total_risk: f64,
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// A system by ID, or by name ignoring case.
+    async fn system(
+        &self,
+        ctx: &Context<'_>,
+        id: Option<i64>,
+        name: Option<String>,
+    ) -> Result<Option<SystemNode>> {
+        let graph = graph(ctx)?.clone();
+        let system = match (id, name) {
+            (Some(id), _) => database::get_system(graph, id).await?,
+            (None, Some(name)) => database::find_system_by_name(graph, &name).await?,
+            (None, None) => return Err(Error::new("either id or name is required")),
+        };
+        Ok(system.map(SystemNode))
+    }
+
+    /// Systems whose names match a search, best first.
+    async fn systems(
+        &self,
+        ctx: &Context<'_>,
+        search: String,
+        limit: Option<usize>,
+    ) -> Result<Vec<SystemNode>> {
+        let graph = graph(ctx)?.clone();
+        let names = database::get_system_names(graph.clone()).await?;
+        let limit = limit.unwrap_or(DEFAULT_SYSTEMS_LIMIT);
+        let found: Vec<i64> = search_systems(&names, &search, limit)
+            .iter()
+            .map(|found| found.system_id)
+            .collect();
+        let mut systems: HashMap<i64, System> = database::get_systems_by_id(graph, found.clone())
+            .await?
+            .into_iter()
+            .map(|system| (system.system_id, system))
+            .collect();
+        Ok(found
+            .iter()
+            .filter_map(|system_id| systems.remove(system_id))
+            .map(SystemNode)
+            .collect())
+    }
+
+    /// A route between two systems, given by name or ID. Systems in `avoid` are only passed
+    /// through when there's no other way, and wormholes too small for `shipSize` aren't used.
+    #[allow(clippy::too_many_arguments)]
+    async fn route(
+        &self,
+        ctx: &Context<'_>,
+        from: String,
+        to: String,
+        #[graphql(default)] mode: RouteMode,
+        #[graphql(default)] avoid: Vec<String>,
+        ship_size: Option<ShipSize>,
+        #[graphql(default)] bridges: bool,
+        #[graphql(default = true)] wormholes: bool,
+        #[graphql(default)] avoid_gates: Vec<GateType>,
+        risk_model: Option<RiskModel>,
+    ) -> Result<Option<Route>> {
+        let graph = graph(ctx)?.clone();
+        let names = database::get_system_names(graph.clone()).await?;
+        let resolve = |input: &str| {
+            resolve_system(&names, input).map_err(|unresolved| {
+                let (code, message) = match unresolved.reason {
+                    UnresolvedReason::Unknown => ("unknown_system", "unknown system"),
+                    UnresolvedReason::Ambiguous => ("ambiguous_system", "ambiguous system"),
+                };
+                Error::new(format!("{message}: {}", unresolved.input)).extend_with(
+                    |_, extensions| {
+                        extensions.set("code", code);
+                        extensions.set("suggestions", unresolved.suggestions.clone());
+                    },
+                )
+            })
+        };
+        let from = resolve(&from)?;
+        let to = resolve(&to)?;
+        let avoided: HashSet<String> = avoid
+            .iter()
+            .map(|system| resolve(system))
+            .collect::<Result<_>>()?;
+
+        let options = RouteOptions {
+            bridges,
+            wormholes,
+            avoid_gates: avoid_gates.into_iter().map(Into::into).collect(),
+        };
+        let risk_model = risk_model.map_or_else(RiskModelKind::configured, Into::into);
+        let system_risks = database::get_system_risks(graph.clone(), risk_model).await?;
+        let route = if avoided.is_empty() && ship_size.is_none() {
+            match mode {
+                RouteMode::Shortest => {
+                    database::find_shortest_route(graph.clone(), from, to, &options).await?
+                }
+                RouteMode::Safest => {
+                    if !database::graph_exists(&graph, String::from("jump-risk")).await? {
+                        database::build_jump_risk_graph(graph.clone()).await?;
+                    }
+                    database::find_safest_route(graph.clone(), from, to, &options, risk_model)
+                        .await?
+                }
+            }
+        } else {
+            // The route projections can't leave out single systems or wormholes by size, so
+            // these routes are searched over the connections that fit.
+            let systems = database::get_all_systems(graph.clone()).await?;
+            let connections = database::get_system_connections_for_ship(
+                graph.clone(),
+                &options,
+                ship_size.map(Into::into),
+            )
+            .await?;
+            let costs = route_costs(&systems, &system_risks, mode, &avoided);
+            find_lowest_risk_route(&systems, &connections, &costs, &from, &to)
+        };
+        let Some(route) = route else {
+            return Ok(None);
+        };
+        let ids: HashMap<&str, i64> = names
+            .iter()
+            .map(|(system_id, name)| (name.as_str(), *system_id))
+            .collect();
+        let total_risk = route
+            .iter()
+            .skip(1)
+            .filter_map(|name| system_risks.get(ids.get(name.as_str())?))
+            .sum();
+
+        Ok(Some(Route {
+            mode,
+            jumps: route.len().saturating_sub(1),
+            systems: route,
+            total_risk,
+        }))
+    }
+}
+
+/// How much jumping into each system costs a route of the given mode. Every jump costs the same
+/// for the shortest route and the system's risk for the safest one, and avoided systems cost so
+/// much they're only used when there's no other way.
+fn route_costs(
+    systems: &[System],
+    risks: &HashMap<i64, f64>,
+    mode: RouteMode,
+    avoided: &HashSet<String>,
+) -> HashMap<i64, f64> {
+    systems
+        .iter()
+        .map(|system| {
+            let cost = match mode {
+                RouteMode::Shortest => 1.0,
+                RouteMode::Safest => risks.get(&system.system_id).copied().unwrap_or_default(),
+            };
+            let avoided_cost = if avoided.contains(&system.name) {
+                crate::camp::CAMP_RISK
+            } else {
+                0.0
+            };
+            (system.system_id, cost + avoided_cost)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn system(system_id: i64, name: &str) -> System {
+        System {
+            name: name.to_string(),
+            security_status: 0.5,
+            system_id,
+            ..System::default()
+        }
+    }
+
+    #[test]
+    fn test_avoided_systems_are_routed_around() {
+        let systems = [
+            system(1, "Jita"),
+            system(2, "Niyabainen"),
+            system(3, "Perimeter"),
+            system(4, "Urlen"),
+        ];
+        // Jita reaches Urlen through either Niyabainen or the longer way through Perimeter.
+        let connections = [(1, 2), (2, 4), (1, 3), (3, 4)];
+        let risks = HashMap::from([(2, 0.1), (3, 0.5), (4, 0.1)]);
+        let avoided = HashSet::from([String::from("Niyabainen")]);
+
+        let costs = route_costs(&systems, &risks, RouteMode::Shortest, &avoided);
+        let route = find_lowest_risk_route(&systems, &connections, &costs, "Jita", "Urlen");
+
+        assert_eq!(route.unwrap(), vec!["Jita", "Perimeter", "Urlen"]);
+    }
+
+    #[test]
+    fn test_schema_exposes_route_arguments() {
+        let sdl = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .finish()
+            .sdl();
+
+        assert!(sdl.contains("avoid: [String!]! = []"));
+        assert!(sdl.contains("shipSize: ShipSize"));
+        assert!(sdl.contains("constellation: Constellation!"));
+    }
+
+    #[tokio::test]
+    async fn test_limits_reject_deep_queries_but_not_introspection() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .limit_depth(MAX_QUERY_DEPTH)
+            .limit_complexity(MAX_QUERY_COMPLEXITY)
+            .finish();
+        let introspection = "{ __schema { types { fields { type { ...TypeRef } } } } }
+            fragment TypeRef on __Type { kind name ofType { kind name ofType { kind name
+                ofType { kind name ofType { kind name ofType { kind name ofType { kind name
+                ofType { kind name ofType { kind name ofType { kind name } } } } } } } } } }";
+        assert!(schema.execute(introspection).await.errors.is_empty());
+
+        let walk = "{ system(id: 30000142) { neighbors { system { neighbors { system {
+            neighbors { system { neighbors { system { neighbors { system { neighbors {
+            system { neighbors { system { neighbors { system { name } } } } } } } } } } } }
+            } } } } } }";
+        let errors = schema.execute(walk).await.errors;
+        assert_eq!(errors[0].message, "Query is nested too deep.");
+    }
+}
@@ -0,0 +1,97 @@
+use crate::database::JumpBridge;
+
+/// The separators that tools commonly put between the two ends of a jump bridge.
+const SEPARATORS: [&str; 4] = ["»", "-->", "->", ">>"];
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+#[error("line {line} is not a jump bridge: {text}")]
+pub struct ParseError {
+    pub line: usize,
+    pub text: String,
+}
+
+/// Parses jump bridges from the common "system » system" paste format, one bridge per line, as
+/// exported from Ansiblex structure names and map tools. Each end may carry a planet and moon
+/// after an `@`, the destination may be followed by ` - ` and the structure's name, and the line
+/// may start with the structure ID. Blank lines are ignored.
+pub fn parse_jump_bridges(text: &str, owner: Option<&str>) -> Result<Vec<JumpBridge>, ParseError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            parse_jump_bridge(line, owner).ok_or_else(|| ParseError {
+                line: index + 1,
+                text: line.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn parse_jump_bridge(line: &str, owner: Option<&str>) -> Option<JumpBridge> {
+    let (from, to) = SEPARATORS
+        .iter()
+        .find_map(|separator| line.split_once(separator))?;
+
+    let mut from = from.trim();
+    let mut structure_id = None;
+    if let Some((first, rest)) = from.split_once(char::is_whitespace)
+        && let Ok(id) = first.parse::<i64>()
+    {
+        structure_id = Some(id);
+        from = rest.trim();
+    }
+    let to = to.split(" - ").next().unwrap_or_default();
+
+    let from_system_name = system_name(from)?;
+    let to_system_name = system_name(to)?;
+    Some(JumpBridge {
+        from_system_name,
+        to_system_name,
+        owner: owner.map(String::from),
+        structure_id,
+    })
+}
+
+fn system_name(end: &str) -> Option<String> {
+    let name = end.split('@').next().unwrap_or_default().trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_structure_name_format() {
+        let bridges =
+            parse_jump_bridges("1DQ1-A » 8WA-Z6 - Keepstar Express", Some("Goons")).unwrap();
+
+        assert_eq!(bridges.len(), 1);
+        assert_eq!(bridges[0].from_system_name, "1DQ1-A");
+        assert_eq!(bridges[0].to_system_name, "8WA-Z6");
+        assert_eq!(bridges[0].owner.as_deref(), Some("Goons"));
+        assert_eq!(bridges[0].structure_id, None);
+    }
+
+    #[test]
+    fn test_parse_planet_moon_and_structure_id() {
+        let text = "\n1035466617946 5ZXX-K @ 1-3 --> Y-2ANO @ 6-1\n\n";
+        let bridges = parse_jump_bridges(text, None).unwrap();
+
+        assert_eq!(bridges[0].from_system_name, "5ZXX-K");
+        assert_eq!(bridges[0].to_system_name, "Y-2ANO");
+        assert_eq!(bridges[0].structure_id, Some(1035466617946));
+    }
+
+    #[test]
+    fn test_parse_reports_the_bad_line() {
+        let error = parse_jump_bridges("1DQ1-A » 8WA-Z6\nnot a bridge", None).unwrap_err();
+
+        assert_eq!(error.line, 2);
+        assert_eq!(error.text, "not a bridge");
+    }
+}
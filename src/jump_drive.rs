@@ -0,0 +1,659 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::System;
+use crate::dijkstra::dijkstra;
+
+pub const METERS_PER_LIGHT_YEAR: f64 = 9_460_730_472_580_800.0;
+
+/// Systems in known space have ids below this value, wormhole space (including Thera) above it.
+const WORMHOLE_SPACE_START_ID: i64 = 31_000_000;
+
+/// Zarzakh does not allow jump drives to be used into it.
+const ZARZAKH_SYSTEM_ID: i64 = 30_100_000;
+
+/// The constellations that make up Pochven, which cannot be reached with a jump drive.
+const POCHVEN_CONSTELLATION_IDS: [i64; 3] = [20_000_788, 20_000_789, 20_000_790];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Hull {
+    BlackOps,
+    Carrier,
+    Dreadnought,
+    ForceAuxiliary,
+    JumpFreighter,
+    Rorqual,
+    Supercarrier,
+    Titan,
+}
+
+impl Hull {
+    pub const ALL: [Hull; 8] = [
+        Hull::BlackOps,
+        Hull::Carrier,
+        Hull::Dreadnought,
+        Hull::ForceAuxiliary,
+        Hull::JumpFreighter,
+        Hull::Rorqual,
+        Hull::Supercarrier,
+        Hull::Titan,
+    ];
+
+    /// Jump range in light-years without Jump Drive Calibration trained.
+    pub fn base_range(&self) -> f64 {
+        match self {
+            Hull::BlackOps => 4.0,
+            Hull::JumpFreighter | Hull::Rorqual => 5.0,
+            Hull::Carrier
+            | Hull::Dreadnought
+            | Hull::ForceAuxiliary
+            | Hull::Supercarrier
+            | Hull::Titan => 3.5,
+        }
+    }
+
+    /// Jump range in light-years, with each level of Jump Drive Calibration adding 20%.
+    pub fn max_range(&self, jump_drive_calibration: u8) -> f64 {
+        self.base_range() * (1.0 + 0.2 * f64::from(jump_drive_calibration.min(5)))
+    }
+
+    /// Supercarriers and titans are too large to use stargates.
+    pub fn can_use_stargates(&self) -> bool {
+        !matches!(self, Hull::Supercarrier | Hull::Titan)
+    }
+}
+
+pub fn light_years_between(from: &System, to: &System) -> f64 {
+    let dx = from.x - to.x;
+    let dy = from.y - to.y;
+    let dz = from.z - to.z;
+    (dx * dx + dy * dy + dz * dz).sqrt() / METERS_PER_LIGHT_YEAR
+}
+
+/// Whether a capital ship can use its jump drive to land in the given system. A cyno can't be lit
+/// in high-sec, wormhole space, Pochven or Zarzakh.
+pub fn is_jump_drive_destination(system: &System) -> bool {
+    !system.is_high_sec()
+        && system.system_id < WORMHOLE_SPACE_START_ID
+        && system.system_id != ZARZAKH_SYSTEM_ID
+        && !POCHVEN_CONSTELLATION_IDS.contains(&system.constellation_id)
+}
+
+/// Jump fatigue can't build up beyond five hours.
+pub const MAX_FATIGUE_MINUTES: f64 = 300.0;
+
+/// The jump activation timer never exceeds thirty minutes.
+const MAX_REACTIVATION_MINUTES: f64 = 30.0;
+
+/// Jump fatigue is never calculated from less than ten minutes of existing fatigue.
+const MIN_FATIGUE_BASE_MINUTES: f64 = 10.0;
+
+/// A rough allowance for the time it takes to use a stargate, including the session change.
+const STARGATE_JUMP_MINUTES: f64 = 1.0;
+
+/// When searching for the least fatigue, a way into a system has to beat every other way found
+/// into it by this many minutes of fatigue or activation timer to be kept. Otherwise routes that
+/// hop back and forth to shave seconds off would keep the search going for a long time.
+const FATIGUE_SEARCH_RESOLUTION_MINUTES: f64 = 1.0;
+
+impl Hull {
+    /// The reduction to the distance used when calculating jump fatigue.
+    pub fn fatigue_reduction(&self) -> f64 {
+        match self {
+            Hull::JumpFreighter | Hull::Rorqual => 0.9,
+            Hull::BlackOps => 0.75,
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteOptimization {
+    /// The fewest jumps, regardless of fatigue.
+    #[default]
+    Jumps,
+    /// The least jump fatigue on arrival.
+    Fatigue,
+    /// The earliest arrival, including time spent waiting on the jump activation timer.
+    Time,
+}
+
+impl RouteOptimization {
+    pub const ALL: [RouteOptimization; 3] = [
+        RouteOptimization::Jumps,
+        RouteOptimization::Fatigue,
+        RouteOptimization::Time,
+    ];
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct JumpPlanOptions {
+    pub hull: Hull,
+    pub jump_drive_calibration: u8,
+    pub use_stargates: bool,
+    pub starting_fatigue_minutes: f64,
+    pub optimization: RouteOptimization,
+}
+
+/// The state of a pilot's jump timers at some point along a route. All values are in minutes,
+/// with `elapsed_minutes` counted from the start of the route.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PilotState {
+    pub elapsed_minutes: f64,
+    pub fatigue_minutes: f64,
+    pub reactivation_ends_at: f64,
+}
+
+impl PilotState {
+    pub fn new(starting_fatigue_minutes: f64) -> Self {
+        Self {
+            elapsed_minutes: 0.0,
+            fatigue_minutes: starting_fatigue_minutes.clamp(0.0, MAX_FATIGUE_MINUTES),
+            reactivation_ends_at: 0.0,
+        }
+    }
+
+    /// Waits out the jump activation timer, letting fatigue decay in real time meanwhile.
+    fn wait_for_reactivation(&self) -> Self {
+        let waited = (self.reactivation_ends_at - self.elapsed_minutes).max(0.0);
+        Self {
+            elapsed_minutes: self.elapsed_minutes + waited,
+            fatigue_minutes: (self.fatigue_minutes - waited).max(0.0),
+            reactivation_ends_at: self.reactivation_ends_at,
+        }
+    }
+
+    /// The state after using a jump drive over the given distance, following the game formulas:
+    /// the activation timer is `max(fatigue / 10, 1 + effective ly)` minutes and the new fatigue
+    /// is `max(fatigue, 10) * (1 + effective ly)` minutes.
+    pub fn after_jump_drive(&self, light_years: f64, hull: Hull) -> Self {
+        let ready = self.wait_for_reactivation();
+        let effective_light_years = light_years * (1.0 - hull.fatigue_reduction());
+        let reactivation = (ready.fatigue_minutes / 10.0)
+            .max(1.0 + effective_light_years)
+            .min(MAX_REACTIVATION_MINUTES);
+        let fatigue = (ready.fatigue_minutes.max(MIN_FATIGUE_BASE_MINUTES)
+            * (1.0 + effective_light_years))
+            .min(MAX_FATIGUE_MINUTES);
+        Self {
+            elapsed_minutes: ready.elapsed_minutes,
+            fatigue_minutes: fatigue,
+            reactivation_ends_at: ready.elapsed_minutes + reactivation,
+        }
+    }
+
+    /// The state after taking a stargate, which adds no fatigue but still has to wait on the
+    /// jump activation timer.
+    pub fn after_stargate(&self) -> Self {
+        let ready = self.wait_for_reactivation();
+        Self {
+            elapsed_minutes: ready.elapsed_minutes + STARGATE_JUMP_MINUTES,
+            fatigue_minutes: (ready.fatigue_minutes - STARGATE_JUMP_MINUTES).max(0.0),
+            reactivation_ends_at: ready.reactivation_ends_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LegKind {
+    Stargate,
+    JumpDrive,
+}
+
+impl LegKind {
+    pub const ALL: [LegKind; 2] = [LegKind::Stargate, LegKind::JumpDrive];
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JumpLeg {
+    pub from: String,
+    pub to: String,
+    pub kind: LegKind,
+    pub light_years: f64,
+    /// Minutes after the start of the route that this leg is taken.
+    pub departs_at_minutes: f64,
+    /// Jump fatigue after the leg.
+    pub fatigue_minutes: f64,
+    /// The jump activation timer started by the leg.
+    pub reactivation_minutes: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JumpRoute {
+    pub systems: Vec<String>,
+    pub legs: Vec<JumpLeg>,
+    pub total_light_years: f64,
+    pub total_minutes: f64,
+    pub final_fatigue_minutes: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Label {
+    jumps: u32,
+    state: PilotState,
+}
+
+impl Label {
+    fn jumps_cost(&self) -> (f64, f64) {
+        (f64::from(self.jumps), self.state.elapsed_minutes)
+    }
+
+    fn time_cost(&self) -> (f64, f64) {
+        (self.state.elapsed_minutes, f64::from(self.jumps))
+    }
+}
+
+impl PilotState {
+    fn reactivation_minutes(&self) -> f64 {
+        (self.reactivation_ends_at - self.elapsed_minutes).max(0.0)
+    }
+
+    /// Whether the pilot is at least as well off as in `other` for every jump still to come.
+    /// Fatigue and the activation timer decay the same way whenever they are carried, so only how
+    /// much of each is left matters.
+    fn dominates(&self, other: &PilotState) -> bool {
+        self.fatigue_minutes <= other.fatigue_minutes + FATIGUE_SEARCH_RESOLUTION_MINUTES
+            && self.reactivation_minutes()
+                <= other.reactivation_minutes() + FATIGUE_SEARCH_RESOLUTION_MINUTES
+    }
+}
+
+/// A way into a system found by the least fatigue search, with the label it came from.
+struct FatigueLabel {
+    system: usize,
+    state: PilotState,
+    previous: Option<(usize, LegKind)>,
+}
+
+/// Whether the route leading to a label has passed through a system.
+fn has_visited(labels: &[FatigueLabel], mut index: usize, system: usize) -> bool {
+    loop {
+        if labels[index].system == system {
+            return true;
+        }
+        match labels[index].previous {
+            Some((previous, _)) => index = previous,
+            None => return false,
+        }
+    }
+}
+
+/// Finds the steps of the route arriving with the least fatigue. Fatigue can drop along a route,
+/// while waiting on timers or taking stargates, so the best way into a system isn't necessarily
+/// the best way on from it. Every system therefore keeps each way in that no other beats on both
+/// fatigue and activation timer. Routes are extended a jump at a time, so of two equal ways in the
+/// one with fewer jumps is found first and kept. Routes never pass through a system twice, since
+/// going in circles only lets fatigue wear off the way waiting in place would.
+fn least_fatigue_steps(
+    system_count: usize,
+    source: usize,
+    target: usize,
+    start: PilotState,
+    legs: impl Fn(usize) -> Vec<(usize, LegKind)>,
+    advance: impl Fn(usize, LegKind, usize, &PilotState) -> PilotState,
+) -> Option<Vec<(usize, LegKind, usize)>> {
+    let mut labels = vec![FatigueLabel {
+        system: source,
+        state: start,
+        previous: None,
+    }];
+    let mut kept: Vec<Vec<usize>> = vec![Vec::new(); system_count];
+    kept[source].push(0);
+    let mut frontier = vec![0];
+
+    while !frontier.is_empty() {
+        let mut next_frontier = Vec::new();
+        for index in frontier {
+            let from = labels[index].system;
+            for (next, kind) in legs(from) {
+                if has_visited(&labels, index, next) {
+                    continue;
+                }
+                let state = advance(from, kind, next, &labels[index].state);
+                if kept[next]
+                    .iter()
+                    .any(|&other| labels[other].state.dominates(&state))
+                {
+                    continue;
+                }
+                kept[next].push(labels.len());
+                next_frontier.push(labels.len());
+                labels.push(FatigueLabel {
+                    system: next,
+                    state,
+                    previous: Some((index, kind)),
+                });
+            }
+        }
+        frontier = next_frontier;
+    }
+
+    let mut current = kept[target].iter().copied().reduce(|best, index| {
+        if labels[index].state.fatigue_minutes < labels[best].state.fatigue_minutes {
+            index
+        } else {
+            best
+        }
+    })?;
+    let mut steps = Vec::new();
+    while let Some((previous, kind)) = labels[current].previous {
+        steps.push((labels[previous].system, kind, labels[current].system));
+        current = previous;
+    }
+    steps.reverse();
+    Some(steps)
+}
+
+/// Finds the best route between two systems for the chosen optimization, using the jump drive
+/// between any two systems in range and, when allowed, the stargate connections between systems.
+/// Jumps and time only grow along a route, so for those each system keeps only its best label.
+/// That is exact for jumps and close for time, where an earlier arrival with more fatigue can in
+/// rare cases be overtaken by a later one whose activation timers are shorter.
+pub fn plan_route(
+    systems: &[System],
+    stargate_connections: &[(i64, i64)],
+    from_system_name: &str,
+    to_system_name: &str,
+    options: &JumpPlanOptions,
+) -> Option<JumpRoute> {
+    let source = systems.iter().position(|s| s.name == from_system_name)?;
+    let target = systems.iter().position(|s| s.name == to_system_name)?;
+
+    let index_by_id: HashMap<i64, usize> = systems
+        .iter()
+        .enumerate()
+        .map(|(index, system)| (system.system_id, index))
+        .collect();
+    let mut gate_neighbors: Vec<Vec<usize>> = vec![Vec::new(); systems.len()];
+    if options.use_stargates && options.hull.can_use_stargates() {
+        for (from, to) in stargate_connections {
+            if let (Some(&from), Some(&to)) = (index_by_id.get(from), index_by_id.get(to)) {
+                gate_neighbors[from].push(to);
+            }
+        }
+    }
+    let jump_destinations: Vec<usize> = (0..systems.len())
+        .filter(|&index| is_jump_drive_destination(&systems[index]))
+        .collect();
+    let max_range = options.hull.max_range(options.jump_drive_calibration);
+
+    let next_legs = |index: usize| -> Vec<(usize, LegKind)> {
+        let gate_jumps = gate_neighbors[index]
+            .iter()
+            .map(|&next| (next, LegKind::Stargate));
+        let drive_jumps = jump_destinations
+            .iter()
+            .filter(|&&next| {
+                next != index && light_years_between(&systems[index], &systems[next]) <= max_range
+            })
+            .map(|&next| (next, LegKind::JumpDrive));
+        gate_jumps.chain(drive_jumps).collect()
+    };
+    let advance = |from: usize, kind: LegKind, to: usize, state: &PilotState| match kind {
+        LegKind::Stargate => state.after_stargate(),
+        LegKind::JumpDrive => state.after_jump_drive(
+            light_years_between(&systems[from], &systems[to]),
+            options.hull,
+        ),
+    };
+
+    let start = Label {
+        jumps: 0,
+        state: PilotState::new(options.starting_fatigue_minutes),
+    };
+    let cheapest_steps = |cost: fn(&Label) -> (f64, f64)| {
+        dijkstra(
+            systems.len(),
+            source,
+            start,
+            |index| index == target,
+            cost,
+            |index, label| {
+                next_legs(index)
+                    .into_iter()
+                    .map(|(next, kind)| {
+                        let next_label = Label {
+                            jumps: label.jumps + 1,
+                            state: advance(index, kind, next, &label.state),
+                        };
+                        (next, kind, next_label)
+                    })
+                    .collect()
+            },
+        )
+        .map(|path| path.steps)
+    };
+    let steps = match options.optimization {
+        RouteOptimization::Jumps => cheapest_steps(Label::jumps_cost)?,
+        RouteOptimization::Time => cheapest_steps(Label::time_cost)?,
+        RouteOptimization::Fatigue => least_fatigue_steps(
+            systems.len(),
+            source,
+            target,
+            start.state,
+            next_legs,
+            advance,
+        )?,
+    };
+
+    // Replay the route from the start so every leg reports the timers the pilot will see.
+    let mut state = start.state;
+    let mut legs = Vec::with_capacity(steps.len());
+    for (from, kind, to) in steps {
+        let light_years = light_years_between(&systems[from], &systems[to]);
+        let next = match kind {
+            LegKind::Stargate => state.after_stargate(),
+            LegKind::JumpDrive => state.after_jump_drive(light_years, options.hull),
+        };
+        let departs_at_minutes = match kind {
+            LegKind::Stargate => next.elapsed_minutes - STARGATE_JUMP_MINUTES,
+            LegKind::JumpDrive => next.elapsed_minutes,
+        };
+        legs.push(JumpLeg {
+            from: systems[from].name.clone(),
+            to: systems[to].name.clone(),
+            kind,
+            light_years: match kind {
+                LegKind::Stargate => 0.0,
+                LegKind::JumpDrive => light_years,
+            },
+            departs_at_minutes,
+            fatigue_minutes: next.fatigue_minutes,
+            reactivation_minutes: next.reactivation_ends_at - next.elapsed_minutes,
+        });
+        state = next;
+    }
+
+    let mut route_systems = vec![systems[source].name.clone()];
+    route_systems.extend(legs.iter().map(|leg| leg.to.clone()));
+    Some(JumpRoute {
+        systems: route_systems,
+        total_light_years: legs.iter().map(|leg| leg.light_years).sum(),
+        total_minutes: state.elapsed_minutes,
+        final_fatigue_minutes: state.fatigue_minutes,
+        legs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn system(system_id: i64, name: &str, security_status: f64, x_light_years: f64) -> System {
+        System {
+            name: name.to_string(),
+            x: x_light_years * METERS_PER_LIGHT_YEAR,
+            security_status,
+            system_id,
+            ..System::default()
+        }
+    }
+
+    fn options(hull: Hull, use_stargates: bool) -> JumpPlanOptions {
+        JumpPlanOptions {
+            hull,
+            jump_drive_calibration: 5,
+            use_stargates,
+            starting_fatigue_minutes: 0.0,
+            optimization: RouteOptimization::Jumps,
+        }
+    }
+
+    #[test]
+    fn test_max_range_applies_jump_drive_calibration() {
+        assert_eq!(Hull::Carrier.max_range(0), 3.5);
+        assert_eq!(Hull::Carrier.max_range(5), 7.0);
+        assert_eq!(Hull::JumpFreighter.max_range(5), 10.0);
+        // Levels above five are treated as five.
+        assert_eq!(Hull::BlackOps.max_range(9), 8.0);
+    }
+
+    #[test]
+    fn test_jump_drive_cannot_enter_high_sec_or_wormhole_space() {
+        assert!(!is_jump_drive_destination(&system(
+            30000142, "Jita", 0.9, 0.0
+        )));
+        assert!(!is_jump_drive_destination(&system(
+            31000005, "Thera", -1.0, 0.0
+        )));
+        assert!(is_jump_drive_destination(&system(
+            30002813, "Tama", 0.3, 0.0
+        )));
+    }
+
+    #[test]
+    fn test_plan_route_hops_through_systems_in_range() {
+        let systems = vec![
+            system(30000001, "A", 0.3, 0.0),
+            system(30000002, "B", -0.2, 6.0),
+            system(30000003, "C", 0.1, 12.0),
+        ];
+
+        let route = plan_route(&systems, &[], "A", "C", &options(Hull::Carrier, false)).unwrap();
+
+        assert_eq!(route.systems, vec!["A", "B", "C"]);
+        assert!(route.legs.iter().all(|leg| leg.kind == LegKind::JumpDrive));
+        assert!((route.legs[0].light_years - 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_plan_route_uses_gates_into_high_sec() {
+        let systems = vec![
+            system(30000001, "A", 0.3, 0.0),
+            system(30000002, "B", 0.9, 1.0),
+        ];
+        let connections = [(30000001, 30000002), (30000002, 30000001)];
+
+        assert!(
+            plan_route(
+                &systems,
+                &connections,
+                "A",
+                "B",
+                &options(Hull::Carrier, false)
+            )
+            .is_none()
+        );
+        let route = plan_route(
+            &systems,
+            &connections,
+            "A",
+            "B",
+            &options(Hull::Carrier, true),
+        )
+        .unwrap();
+        assert_eq!(route.legs[0].kind, LegKind::Stargate);
+        assert!(
+            plan_route(
+                &systems,
+                &connections,
+                "A",
+                "B",
+                &options(Hull::Titan, true)
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_first_jump_fatigue_and_reactivation() {
+        // A fresh carrier jumping 5 ly: max(0, 10) * (1 + 5) = 60 minutes of fatigue and a
+        // max(0 / 10, 1 + 5) = 6 minute activation timer.
+        let state = PilotState::new(0.0).after_jump_drive(5.0, Hull::Carrier);
+        assert_eq!(state.fatigue_minutes, 60.0);
+        assert_eq!(state.reactivation_ends_at, 6.0);
+    }
+
+    #[test]
+    fn test_second_jump_waits_and_decays_fatigue() {
+        // The second jump waits out the 6 minute timer, leaving 54 minutes of fatigue, which then
+        // grows to 54 * 6 = 324 and is capped at 300. The timer is max(5.4, 6) = 6 minutes.
+        let state = PilotState::new(0.0)
+            .after_jump_drive(5.0, Hull::Carrier)
+            .after_jump_drive(5.0, Hull::Carrier);
+        assert_eq!(state.elapsed_minutes, 6.0);
+        assert_eq!(state.fatigue_minutes, 300.0);
+        assert!((state.reactivation_ends_at - 12.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_jump_freighter_fatigue_reduction() {
+        // 10 ly with a 90% reduction counts as 1 ly: 10 * (1 + 1) = 20 minutes.
+        let state = PilotState::new(0.0).after_jump_drive(10.0, Hull::JumpFreighter);
+        assert!((state.fatigue_minutes - 20.0).abs() < 1e-9);
+        assert!((state.reactivation_ends_at - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_plan_route_reports_accumulated_fatigue() {
+        let systems = vec![
+            system(30000001, "A", 0.3, 0.0),
+            system(30000002, "B", -0.2, 6.0),
+            system(30000003, "C", 0.1, 12.0),
+        ];
+        let mut plan_options = options(Hull::Carrier, false);
+        plan_options.starting_fatigue_minutes = 30.0;
+
+        let route = plan_route(&systems, &[], "A", "C", &plan_options).unwrap();
+
+        // 30 * 7 = 210 minutes after the first jump, which has a 7 minute timer.
+        assert!((route.legs[0].fatigue_minutes - 210.0).abs() < 1e-9);
+        assert!((route.legs[0].reactivation_minutes - 7.0).abs() < 1e-9);
+        assert!((route.legs[1].departs_at_minutes - 7.0).abs() < 1e-9);
+        assert_eq!(route.final_fatigue_minutes, 300.0);
+        assert!((route.total_light_years - 12.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fatigue_optimization_takes_more_jumps_for_less_fatigue() {
+        // A carrier can jump straight from A to C, or take the gates through B, which adds no
+        // fatigue and lets the starting fatigue wear off.
+        let systems = vec![
+            system(30000001, "A", 0.3, 0.0),
+            system(30000002, "B", 0.2, 3.0),
+            system(30000003, "C", 0.1, 7.0),
+        ];
+        let connections = [
+            (30000001, 30000002),
+            (30000002, 30000001),
+            (30000002, 30000003),
+            (30000003, 30000002),
+        ];
+        let mut plan_options = options(Hull::Carrier, true);
+        plan_options.starting_fatigue_minutes = 30.0;
+
+        let fewest = plan_route(&systems, &connections, "A", "C", &plan_options).unwrap();
+        plan_options.optimization = RouteOptimization::Fatigue;
+        let least = plan_route(&systems, &connections, "A", "C", &plan_options).unwrap();
+
+        // The direct jump leaves 30 * (1 + 7) = 240 minutes, the gates 30 - 2 = 28.
+        assert_eq!(fewest.systems, vec!["A", "C"]);
+        assert_eq!(fewest.final_fatigue_minutes, 240.0);
+        assert_eq!(least.systems, vec!["A", "B", "C"]);
+        assert_eq!(least.final_fatigue_minutes, 28.0);
+    }
+}
@@ -1,4 +1,20 @@
+pub mod alerts;
+pub mod api_error;
+pub mod camp;
 pub mod database;
+pub mod dijkstra;
 pub mod esi;
 pub mod eve_scout;
+pub mod events;
+pub mod graphql;
+pub mod jump_bridge;
+pub mod jump_drive;
+pub mod openapi;
+pub mod rally;
+pub mod risk;
+pub mod route_batch;
+pub mod route_export;
+pub mod search;
 pub mod sync;
+pub mod travel_time;
+pub mod zkillboard;
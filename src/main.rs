@@ -1,14 +1,44 @@
+use std::collections::HashMap;
 use std::convert::Infallible;
+use std::num::NonZeroU32;
 use std::sync::Arc;
 
+use async_graphql::http::GraphiQLSource;
+use async_graphql_warp::{GraphQLBadRequest, GraphQLResponse};
+use eve_graph::api_error::{ErrorBody, ErrorCode};
+use eve_graph::camp::{CAMP_RISK, Camp, CampDetection};
 use eve_graph::database::*;
+use eve_graph::events;
+use eve_graph::graphql::{self, EveGraphSchema};
+use eve_graph::jump_bridge::parse_jump_bridges;
+use eve_graph::jump_drive::{self, Hull, JumpPlanOptions, RouteOptimization};
+use eve_graph::openapi;
+use eve_graph::rally::{self, RallyObjective, RallyOptions};
+use eve_graph::risk::{
+    DOWNTIME_HOUR, RiskModelKind, RouteRiskSummary, find_lowest_risk_route, hour_of_day_activity,
+    summarize_route_risk, system_risks,
+};
+use eve_graph::route_batch::{
+    MAX_BATCH_ROUTES, RouteBatchRequest, RouteBatchResult, group_by_source,
+};
+use eve_graph::route_export::{self, ExportFormat, RouteFormat};
+use eve_graph::search::{UnresolvedReason, UnresolvedSystem, resolve_system, search_systems};
 use eve_graph::sync;
 use eve_graph::sync::{
-    refresh_eve_scout_system_relations, refresh_jump_risks, synchronize_esi_stargates,
-    synchronize_esi_systems,
+    evaluate_saved_routes, refresh_eve_scout_system_relations, refresh_jump_risks,
+    synchronize_esi_stargates, synchronize_esi_systems,
 };
+use eve_graph::travel_time::{
+    Ship, ShipProfile, TravelTime, estimate_travel_time, plan_fastest_route,
+};
+use eve_graph::zkillboard;
+use futures_util::stream;
 use neo4rs::Graph;
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinSet;
 use tracing::{error, info};
 use warp::hyper::StatusCode;
 use warp::reject::Reject;
@@ -33,17 +63,47 @@ async fn main() {
         return;
     }
 
+    if let Some(url) = zkillboard::configured_url() {
+        tokio::spawn(sync::ingest_kill_feed(client.clone(), graph.clone(), url));
+    }
+
     // --- Define API Routes ---
     let shortest_route = warp::path!("shortest-route" / String / "to" / String)
         .and(warp::get())
+        .and(warp::query::<RouteOptions>())
+        .and(warp::query::<RouteDetailsQuery>())
         .and(with_graph(graph.clone()))
         .and_then(shortest_route_to_handler);
 
     let safest_route = warp::path!("safest-route" / String / "to" / String)
         .and(warp::get())
+        .and(warp::query::<RouteOptions>())
+        .and(warp::query::<RouteDetailsQuery>())
+        .and(warp::query::<RiskQuery>())
         .and(with_graph(graph.clone()))
         .and_then(safest_route_to_handler);
 
+    let route_comparison = warp::path!("route-comparison" / String / "to" / String)
+        .and(warp::get())
+        .and(warp::query::<RouteOptions>())
+        .and(warp::query::<RiskQuery>())
+        .and(warp::query::<JsonOnlyQuery>())
+        .and(with_graph(graph.clone()))
+        .and_then(route_comparison_handler);
+
+    let fastest_route = warp::path!("fastest-route" / String / "to" / String)
+        .and(warp::get())
+        .and(warp::query::<RouteDetailsQuery>())
+        .and(with_graph(graph.clone()))
+        .and_then(fastest_route_to_handler);
+
+    let jump_route = warp::path!("jump-route" / String / "to" / String)
+        .and(warp::get())
+        .and(warp::query::<JumpRouteQuery>())
+        .and(warp::query::<JsonOnlyQuery>())
+        .and(with_graph(graph.clone()))
+        .and_then(jump_route_handler);
+
     let systems_refresh = warp::path!("systems" / "refresh")
         .and(warp::post())
         .and(with_client(client.clone()))
@@ -68,12 +128,143 @@ async fn main() {
         .and(with_graph(graph.clone()))
         .and_then(wormholes_refresh_handler);
 
+    let systems_within = warp::path!("systems" / String / "within" / u32)
+        .and(warp::get())
+        .and(warp::query::<WithinQuery>())
+        .and(warp::query::<RouteOptions>())
+        .and(with_graph(graph.clone()))
+        .and_then(systems_within_handler);
+
+    let systems_nearest = warp::path!("systems" / String / "nearest")
+        .and(warp::get())
+        .and(warp::query::<NearestQuery>())
+        .and(warp::query::<RouteOptions>())
+        .and(with_graph(graph.clone()))
+        .and_then(systems_nearest_handler);
+
+    let systems_search = warp::path!("systems" / "search")
+        .and(warp::get())
+        .and(warp::query::<SystemSearchQuery>())
+        .and(with_graph(graph.clone()))
+        .and_then(systems_search_handler);
+
+    let system_details = warp::path!("systems" / String)
+        .and(warp::get())
+        .and(with_graph(graph.clone()))
+        .and_then(system_details_handler);
+
+    let route_matrix = warp::path!("route-matrix")
+        .and(warp::post())
+        .and(warp::body::json::<RouteMatrixRequest>())
+        .and(with_graph(graph.clone()))
+        .and_then(route_matrix_handler);
+
+    let route_batch = warp::path!("routes" / "batch")
+        .and(warp::post())
+        .and(warp::body::json::<RouteBatchRequest>())
+        .and(warp::query::<JsonOnlyQuery>())
+        .and(with_graph(graph.clone()))
+        .and_then(route_batch_handler);
+
+    let rally_point = warp::path!("rally-point")
+        .and(warp::post())
+        .and(warp::body::json::<RallyPointRequest>())
+        .and(with_graph(graph.clone()))
+        .and_then(rally_point_handler);
+
+    let jump_bridges_list = warp::path!("jump-bridges")
+        .and(warp::get())
+        .and(with_graph(graph.clone()))
+        .and_then(jump_bridges_list_handler);
+
+    let jump_bridges_add = warp::path!("jump-bridges")
+        .and(warp::post())
+        .and(warp::body::json::<JumpBridge>())
+        .and(with_graph(graph.clone()))
+        .and_then(jump_bridges_add_handler);
+
+    let jump_bridges_import = warp::path!("jump-bridges" / "import")
+        .and(warp::post())
+        .and(warp::query::<JumpBridgeImportQuery>())
+        .and(warp::body::bytes())
+        .and(with_graph(graph.clone()))
+        .and_then(jump_bridges_import_handler);
+
+    let jump_bridges_remove = warp::path!("jump-bridges" / String / "to" / String)
+        .and(warp::delete())
+        .and(with_graph(graph.clone()))
+        .and_then(jump_bridges_remove_handler);
+
+    let camp_alerts = warp::path!("alerts" / "camps")
+        .and(warp::get())
+        .and(warp::query::<CampQuery>())
+        .and(with_graph(graph.clone()))
+        .and_then(camp_alerts_handler);
+
+    let saved_routes_list = warp::path!("saved-routes")
+        .and(warp::get())
+        .and(with_graph(graph.clone()))
+        .and_then(saved_routes_list_handler);
+
+    let saved_routes_add = warp::path!("saved-routes")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_graph(graph.clone()))
+        .and_then(saved_routes_add_handler);
+
+    let saved_routes_remove = warp::path!("saved-routes" / String)
+        .and(warp::delete())
+        .and(with_graph(graph.clone()))
+        .and_then(saved_routes_remove_handler);
+
+    let openapi_document = warp::path!("openapi.json")
+        .and(warp::get())
+        .map(|| warp::reply::json(&openapi::document()));
+
+    let docs = warp::path!("docs")
+        .and(warp::get())
+        .map(|| warp::reply::html(openapi::DOCS_PAGE));
+
+    let graphql_query = warp::path!("graphql")
+        .and(warp::post())
+        .and(async_graphql_warp::graphql(graphql::schema(graph.clone())))
+        .and_then(graphql_handler);
+
+    let graphiql = warp::path!("graphql")
+        .and(warp::get())
+        .map(|| warp::reply::html(GraphiQLSource::build().endpoint("/graphql").finish()));
+
+    let graph_events = warp::path!("events").and(warp::get()).map(events_handler);
+
     let routes = shortest_route
         .or(safest_route)
+        .or(route_comparison)
+        .or(fastest_route)
+        .or(jump_route)
+        .or(systems_within)
+        .or(systems_nearest)
+        .or(systems_search)
+        .or(system_details)
+        .or(route_matrix)
+        .or(route_batch)
+        .or(rally_point)
+        .or(camp_alerts)
         .or(wormholes_refresh)
         .or(systems_refresh)
         .or(systems_risk)
         .or(stargates_refresh)
+        .or(jump_bridges_list)
+        .or(jump_bridges_add)
+        .or(jump_bridges_import)
+        .or(jump_bridges_remove)
+        .or(saved_routes_list)
+        .or(saved_routes_add)
+        .or(saved_routes_remove)
+        .or(graph_events)
+        .or(graphql_query)
+        .or(graphiql)
+        .or(openapi_document)
+        .or(docs)
         .recover(handle_rejection);
 
     info!("Serving routes on 8008");
@@ -84,6 +275,8 @@ async fn main() {
 async fn bootstrap(client: Client, graph: Arc<Graph>) -> Result<(), sync::Error> {
     info!("Bootstrapping application data...");
 
+    create_indexes(graph.clone()).await?;
+
     synchronize_esi_systems(client.clone(), graph.clone()).await?;
     info!("System synchronization complete.");
 
@@ -106,6 +299,29 @@ async fn bootstrap(client: Client, graph: Arc<Graph>) -> Result<(), sync::Error>
     Ok(())
 }
 
+/// Streams graph events to the client as server-sent events, named after the kind of event.
+fn events_handler() -> impl Reply {
+    let stream = stream::unfold(events::subscribe(), |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let sse_event = warp::sse::Event::default()
+                        .event(event.name())
+                        .json_data(&event)
+                        .map_err(|e| error!("Could not serialize graph event: {}", e));
+                    if let Ok(sse_event) = sse_event {
+                        return Some((Ok::<_, Infallible>(sse_event), receiver));
+                    }
+                }
+                // A client too slow to keep up skips the events it missed.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    warp::sse::reply(warp::sse::keep_alive().stream(stream))
+}
+
 fn with_client(client: Client) -> impl Filter<Extract = (Client,), Error = Infallible> + Clone {
     warp::any().map(move || client.clone())
 }
@@ -122,59 +338,542 @@ struct ApiError(sync::Error);
 
 impl Reject for ApiError {}
 
+/// A system named in a request that doesn't pick out exactly one system.
+#[derive(Debug)]
+struct UnknownSystem(UnresolvedSystem);
+
+impl Reject for UnknownSystem {}
+
+/// Resolves a system, given by ID or by name ignoring case, to its name. Every system's name is
+/// only loaded when the input doesn't pick out exactly one system, to find suggestions.
+async fn resolve_system_name(graph: Arc<Graph>, input: &str) -> Result<String, Rejection> {
+    let input = input.trim();
+    let names = find_system_names(graph.clone(), input)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    if let [name] = names.as_slice() {
+        return Ok(name.clone());
+    }
+    let systems = get_system_names(graph)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    resolve_system(&systems, input).map_err(|e| warp::reject::custom(UnknownSystem(e)))
+}
+
+/// Resolves a list of systems, given by ID or by name ignoring case, to their names.
+fn resolve_each_system(
+    systems: &[(i64, String)],
+    inputs: &[String],
+) -> Result<Vec<String>, Rejection> {
+    inputs
+        .iter()
+        .map(|input| {
+            resolve_system(systems, input).map_err(|e| warp::reject::custom(UnknownSystem(e)))
+        })
+        .collect()
+}
+
+/// Resolves the systems at the ends of a route, given by ID or by name ignoring case, to their
+/// names.
+async fn resolve_route_systems(
+    graph: Arc<Graph>,
+    from_system: &str,
+    to_system: &str,
+) -> Result<(String, String), Rejection> {
+    Ok((
+        resolve_system_name(graph.clone(), from_system).await?,
+        resolve_system_name(graph, to_system).await?,
+    ))
+}
+
+/// Responds with the error as JSON, with the status its code calls for and the request ID in the
+/// `x-request-id` header.
+fn error_response(body: ErrorBody) -> warp::reply::Response {
+    let status =
+        StatusCode::from_u16(body.code.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let request_id = body.request_id.clone();
+    reply::with_header(
+        reply::with_status(reply::json(&body), status),
+        "x-request-id",
+        request_id,
+    )
+    .into_response()
+}
+
+fn error_reply(code: ErrorCode, error: impl Into<String>) -> warp::reply::Response {
+    error_response(ErrorBody::new(code, error))
+}
+
+fn unresolved_error(unresolved: &UnresolvedSystem) -> ErrorBody {
+    let body = match unresolved.reason {
+        UnresolvedReason::Unknown => ErrorBody::new(
+            ErrorCode::UnknownSystem,
+            format!("unknown system: {}", unresolved.input),
+        ),
+        UnresolvedReason::Ambiguous => ErrorBody::new(
+            ErrorCode::AmbiguousSystem,
+            format!("ambiguous system: {}", unresolved.input),
+        ),
+    };
+    body.with_suggestions(unresolved.suggestions.clone())
+}
+
 async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
     if err.is_not_found() {
-        return Ok(reply::with_status("NOT_FOUND", StatusCode::NOT_FOUND));
+        return Ok(error_reply(ErrorCode::NotFound, "not found"));
+    }
+
+    if let Some(UnknownSystem(unresolved)) = err.find::<UnknownSystem>() {
+        return Ok(error_response(unresolved_error(unresolved)));
     }
 
     if let Some(api_error) = err.find::<ApiError>() {
-        error!("API Error: {:?}", api_error.0);
-        return Ok(reply::with_status(
-            "INTERNAL_SERVER_ERROR",
-            StatusCode::INTERNAL_SERVER_ERROR,
-        ));
+        let body = ErrorBody::new(ErrorCode::from(&api_error.0), api_error.0.to_string());
+        error!("API Error ({}): {:?}", body.request_id, api_error.0);
+        return Ok(error_response(body));
+    }
+
+    if let Some(e) = err.find::<warp::reject::InvalidQuery>() {
+        return Ok(error_reply(ErrorCode::InvalidRequest, e.to_string()));
+    }
+
+    if let Some(e) = err.find::<warp::filters::body::BodyDeserializeError>() {
+        return Ok(error_reply(ErrorCode::InvalidRequest, e.to_string()));
+    }
+
+    if let Some(GraphQLBadRequest(e)) = err.find::<GraphQLBadRequest>() {
+        return Ok(error_reply(ErrorCode::InvalidRequest, e.to_string()));
+    }
+
+    if let Some(e) = err.find::<warp::reject::MissingHeader>() {
+        return Ok(error_reply(ErrorCode::InvalidRequest, e.to_string()));
+    }
+
+    if let Some(e) = err.find::<warp::reject::InvalidHeader>() {
+        return Ok(error_reply(ErrorCode::InvalidRequest, e.to_string()));
     }
 
-    if let Some(db_error) = err.find::<neo4rs::Error>() {
-        error!("Database Error: {:?}", db_error);
-        return Ok(reply::with_status(
-            "INTERNAL_SERVER_ERROR",
-            StatusCode::INTERNAL_SERVER_ERROR,
+    if let Some(e) = err.find::<warp::reject::MissingCookie>() {
+        return Ok(error_reply(ErrorCode::InvalidRequest, e.to_string()));
+    }
+
+    if let Some(e) = err.find::<warp::reject::LengthRequired>() {
+        return Ok(error_reply(ErrorCode::InvalidRequest, e.to_string()));
+    }
+
+    if let Some(e) = err.find::<warp::reject::PayloadTooLarge>() {
+        return Ok(error_reply(ErrorCode::PayloadTooLarge, e.to_string()));
+    }
+
+    if let Some(e) = err.find::<warp::reject::UnsupportedMediaType>() {
+        return Ok(error_reply(ErrorCode::UnsupportedMediaType, e.to_string()));
+    }
+
+    if err.find::<warp::reject::MethodNotAllowed>().is_some() {
+        return Ok(error_reply(
+            ErrorCode::MethodNotAllowed,
+            "method not allowed",
         ));
     }
 
-    error!("unhandled rejection: {:?}", err);
-    Ok(reply::with_status(
-        "INTERNAL_SERVER_ERROR",
-        StatusCode::INTERNAL_SERVER_ERROR,
-    ))
+    let body = ErrorBody::new(ErrorCode::Internal, "internal server error");
+    error!("unhandled rejection ({}): {:?}", body.request_id, err);
+    Ok(error_response(body))
+}
+
+async fn graphql_handler(
+    (schema, request): (EveGraphSchema, async_graphql::Request),
+) -> Result<GraphQLResponse, Infallible> {
+    Ok(GraphQLResponse::from(schema.execute(request).await))
 }
 
 async fn shortest_route_to_handler(
     from_system_name: String,
     to_system_name: String,
+    options: RouteOptions,
+    details: RouteDetailsQuery,
     graph: Arc<Graph>,
 ) -> Result<impl Reply, Rejection> {
-    match find_shortest_route(graph, from_system_name, to_system_name)
+    let (from_system_name, to_system_name) =
+        resolve_route_systems(graph.clone(), &from_system_name, &to_system_name).await?;
+    let risk_model = RiskModelKind::configured();
+    match find_shortest_route(graph.clone(), from_system_name, to_system_name, &options)
         .await
         .map_err(|e| warp::reject::custom(ApiError(e.into())))?
     {
-        None => {
-            // Return a 404 Not Found, which is more idiomatic for a missing resource (the path).
-            let mut res = warp::reply::json(&serde_json::json!({ "error": "route not found" }))
-                .into_response();
-            *res.status_mut() = StatusCode::NOT_FOUND;
-            Ok(res)
+        None => Ok(error_reply(ErrorCode::NoRoute, "route not found")),
+        Some(route) => route_reply(graph, route, &details, risk_model).await,
+    }
+}
+
+/// Picks the risk model a safest route is weighted by, falling back to the configured one. A
+/// departure time, either as the hour of the day (EVE time) or as hours after downtime, weighs
+/// the route by the activity usually seen at that time of day instead of the latest activity.
+/// `avoid_camps` keeps the route out of systems with an active gate camp unless there's no other
+/// way.
+#[derive(Debug, Deserialize)]
+struct RiskQuery {
+    risk_model: Option<RiskModelKind>,
+    departure_hour: Option<u32>,
+    hours_after_downtime: Option<u32>,
+    #[serde(default)]
+    avoid_camps: bool,
+}
+
+impl RiskQuery {
+    fn departure_hour(&self) -> Option<u32> {
+        match (self.departure_hour, self.hours_after_downtime) {
+            (Some(hour), _) => Some(hour % 24),
+            (None, Some(hours)) => Some((DOWNTIME_HOUR + hours) % 24),
+            (None, None) => None,
         }
-        Some(route) => Ok(warp::reply::json(&route).into_response()),
     }
 }
 
 async fn safest_route_to_handler(
     from_system_name: String,
     to_system_name: String,
+    options: RouteOptions,
+    details: RouteDetailsQuery,
+    risk: RiskQuery,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    let (from_system_name, to_system_name) =
+        resolve_route_systems(graph.clone(), &from_system_name, &to_system_name).await?;
+    let risk_model = risk.risk_model.unwrap_or_else(RiskModelKind::configured);
+    let departure_hour = risk.departure_hour();
+    let route = match (departure_hour, risk.avoid_camps) {
+        (Some(_), _) | (_, true) => {
+            find_safest_route_in_memory(
+                graph.clone(),
+                &from_system_name,
+                &to_system_name,
+                &options,
+                risk_model,
+                departure_hour,
+                risk.avoid_camps,
+            )
+            .await?
+        }
+        (None, false) => {
+            let exists = graph_exists(&graph, String::from("jump-risk"))
+                .await
+                .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+            if !exists {
+                build_jump_risk_graph(graph.clone())
+                    .await
+                    .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+            }
+
+            find_safest_route(
+                graph.clone(),
+                from_system_name,
+                to_system_name,
+                &options,
+                risk_model,
+            )
+            .await
+            .map_err(|e| warp::reject::custom(ApiError(e.into())))?
+        }
+    };
+
+    match route {
+        None => Ok(error_reply(ErrorCode::NoRoute, "route not found")),
+        Some(route) => route_reply(graph, route, &details, risk_model).await,
+    }
+}
+
+/// Finds the safest route outside of the route projections, for risks that depend on the request:
+/// the activity usually seen at a given hour of the day, and the active gate camps.
+async fn find_safest_route_in_memory(
+    graph: Arc<Graph>,
+    from_system_name: &str,
+    to_system_name: &str,
+    options: &RouteOptions,
+    risk_model: RiskModelKind,
+    departure_hour: Option<u32>,
+    avoid_camps: bool,
+) -> Result<Option<Vec<String>>, Rejection> {
+    let systems = get_all_systems(graph.clone())
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let connections = get_system_connections(graph.clone(), options)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+
+    let mut risks = match departure_hour {
+        Some(hour_of_day) => {
+            let samples = get_hour_of_day_activity_samples(graph.clone(), hour_of_day)
+                .await
+                .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+            let activities = hour_of_day_activity(&samples, hour_of_day);
+            system_risks(&systems, &activities, risk_model)
+        }
+        None => get_system_risks(graph.clone(), risk_model)
+            .await
+            .map_err(|e| warp::reject::custom(ApiError(e.into())))?,
+    };
+    if avoid_camps {
+        for camp in find_active_camps(graph, &CampDetection::default()).await? {
+            *risks.entry(camp.system_id).or_default() += CAMP_RISK;
+        }
+    }
+
+    Ok(find_lowest_risk_route(
+        &systems,
+        &connections,
+        &risks,
+        from_system_name,
+        to_system_name,
+    ))
+}
+
+/// How far back the live kill feed is searched for camps.
+const CAMP_LOOKBACK_SECONDS: i64 = 3 * 3600;
+
+async fn find_active_camps(
+    graph: Arc<Graph>,
+    detection: &CampDetection,
+) -> Result<Vec<Camp>, Rejection> {
+    let now = sync::now_seconds();
+    let kills = get_kills_since(graph.clone(), now - CAMP_LOOKBACK_SECONDS)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let stargates = get_all_stargates(graph)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    Ok(detection.detect(&kills, &stargates, now))
+}
+
+#[derive(Debug, Deserialize)]
+struct CampQuery {
+    /// Kills further apart than this aren't part of the same camp, so it can't be zero.
+    window_minutes: Option<NonZeroU32>,
+    min_kills: Option<u32>,
+    active_minutes: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct CampAlert {
+    system_name: Option<String>,
+    #[serde(flatten)]
+    camp: Camp,
+}
+
+async fn camp_alerts_handler(
+    params: CampQuery,
     graph: Arc<Graph>,
 ) -> Result<impl Reply, Rejection> {
+    let default = CampDetection::default();
+    let detection = CampDetection {
+        window_minutes: params
+            .window_minutes
+            .map_or(default.window_minutes, NonZeroU32::get),
+        min_kills: params.min_kills.unwrap_or(default.min_kills),
+        active_minutes: params.active_minutes.unwrap_or(default.active_minutes),
+    };
+
+    let camps = find_active_camps(graph.clone(), &detection).await?;
+    let system_ids = camps.iter().map(|camp| camp.system_id).collect();
+    let names: HashMap<i64, String> = get_systems_by_id(graph, system_ids)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?
+        .into_iter()
+        .map(|system| (system.system_id, system.name))
+        .collect();
+    let alerts: Vec<CampAlert> = camps
+        .into_iter()
+        .map(|camp| CampAlert {
+            system_name: names.get(&camp.system_id).cloned(),
+            camp,
+        })
+        .collect();
+    Ok(warp::reply::json(&alerts))
+}
+
+/// Extra details to include with a route. The ship to estimate travel time for is either one of
+/// the built-in ship classes or a custom warp speed and align time, which override the ship's own.
+/// `legs` lists how to take every hop, including which stargate to use, and `risk` sums up how
+/// risky the route is.
+#[derive(Debug, Deserialize)]
+struct RouteDetailsQuery {
+    ship: Option<Ship>,
+    warp_speed: Option<f64>,
+    align_time: Option<f64>,
+    #[serde(default)]
+    legs: bool,
+    #[serde(default)]
+    risk: bool,
+    #[serde(default)]
+    format: RouteFormat,
+}
+
+impl RouteDetailsQuery {
+    fn profile(&self) -> Option<ShipProfile> {
+        let base = self.ship.map(|ship| ship.profile());
+        if base.is_none() && self.warp_speed.is_none() && self.align_time.is_none() {
+            return None;
+        }
+        let base = base.unwrap_or(Ship::Frigate.profile());
+        Some(ShipProfile {
+            warp_speed: self.warp_speed.unwrap_or(base.warp_speed),
+            align_time: self.align_time.unwrap_or(base.align_time),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct RouteResponse {
+    route: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    travel_time: Option<TravelTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    legs: Option<Vec<RouteLeg>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    risk: Option<RouteRiskSummary>,
+}
+
+/// Replies with the route rendered in a format other pilots' tools take directly.
+async fn export_route(
+    graph: Arc<Graph>,
+    route: &[String],
+    format: ExportFormat,
+) -> Result<warp::reply::Response, Rejection> {
+    let systems = get_route_system_ids(graph, route)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+
+    Ok(match format {
+        ExportFormat::ChatLink => route_export::chat_link(&systems).into_response(),
+        ExportFormat::Waypoints => {
+            warp::reply::json(&route_export::waypoints(&systems)).into_response()
+        }
+        ExportFormat::Dotlan => route_export::dotlan_url(&systems).into_response(),
+    })
+}
+
+/// The format of an endpoint that only answers with JSON. It's read so that asking for another
+/// format is refused rather than ignored.
+#[derive(Debug, Deserialize)]
+struct JsonOnlyQuery {
+    #[serde(default)]
+    format: RouteFormat,
+}
+
+impl JsonOnlyQuery {
+    fn refusal(&self) -> Option<warp::reply::Response> {
+        if self.format == RouteFormat::Json {
+            return None;
+        }
+        Some(error_reply(
+            ErrorCode::InvalidRequest,
+            format!(
+                "this endpoint only answers with json, not {}",
+                self.format.as_str()
+            ),
+        ))
+    }
+}
+
+/// Replies with the bare route, or with the route and the details that were asked for.
+async fn route_reply(
+    graph: Arc<Graph>,
+    route: Vec<String>,
+    details: &RouteDetailsQuery,
+    risk_model: RiskModelKind,
+) -> Result<warp::reply::Response, Rejection> {
+    if let Some(format) = details.format.export_format() {
+        return export_route(graph, &route, format).await;
+    }
+    let ship = details.profile();
+    if ship.is_none() && !details.legs && !details.risk {
+        return Ok(warp::reply::json(&route).into_response());
+    }
+
+    let travel_time = match ship {
+        Some(ship) => {
+            let hops = get_route_stargates(graph.clone(), route.clone())
+                .await
+                .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+            Some(estimate_travel_time(&hops, &ship))
+        }
+        None => None,
+    };
+    let legs = if details.legs {
+        Some(
+            get_route_legs(graph.clone(), route.clone())
+                .await
+                .map_err(|e| warp::reject::custom(ApiError(e.into())))?,
+        )
+    } else {
+        None
+    };
+    let risk = if details.risk {
+        Some(route_risk_summary(graph, &route, risk_model).await?)
+    } else {
+        None
+    };
+    Ok(warp::reply::json(&RouteResponse {
+        route,
+        travel_time,
+        legs,
+        risk,
+    })
+    .into_response())
+}
+
+/// How many of a route's riskiest jumps a risk summary lists.
+const RISKIEST_HOP_COUNT: usize = 3;
+
+async fn route_risk_summary(
+    graph: Arc<Graph>,
+    route: &[String],
+    risk_model: RiskModelKind,
+) -> Result<RouteRiskSummary, Rejection> {
+    let hop_risks = get_route_hop_risks(graph, route.to_vec(), risk_model)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    Ok(summarize_route_risk(route, &hop_risks, RISKIEST_HOP_COUNT))
+}
+
+#[derive(Debug, Serialize)]
+struct ComparedRoute {
+    route: Vec<String>,
+    risk: RouteRiskSummary,
+}
+
+/// The shortest and safest routes side by side, with what taking the safest route costs in jumps
+/// and gains in safety.
+#[derive(Debug, Serialize)]
+struct RouteComparison {
+    shortest: ComparedRoute,
+    safest: ComparedRoute,
+    extra_jumps: i64,
+    catch_probability_reduction: f64,
+}
+
+async fn route_comparison_handler(
+    from_system_name: String,
+    to_system_name: String,
+    options: RouteOptions,
+    risk: RiskQuery,
+    json_only: JsonOnlyQuery,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    if let Some(refusal) = json_only.refusal() {
+        return Ok(refusal);
+    }
+    // Both routes are summarized with the latest risks, which a departure time or camps would
+    // leave the safest route out of step with.
+    if risk.departure_hour().is_some() || risk.avoid_camps {
+        return Ok(error_reply(
+            ErrorCode::InvalidRequest,
+            "route comparison doesn't take departure_hour, hours_after_downtime or avoid_camps",
+        ));
+    }
+    let (from_system_name, to_system_name) =
+        resolve_route_systems(graph.clone(), &from_system_name, &to_system_name).await?;
+    let risk_model = risk.risk_model.unwrap_or_else(RiskModelKind::configured);
     let exists = graph_exists(&graph, String::from("jump-risk"))
         .await
         .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
@@ -184,16 +883,136 @@ async fn safest_route_to_handler(
             .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
     }
 
-    match find_safest_route(graph, from_system_name, to_system_name)
+    let shortest = find_shortest_route(
+        graph.clone(),
+        from_system_name.clone(),
+        to_system_name.clone(),
+        &options,
+    )
+    .await
+    .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let safest = find_safest_route(
+        graph.clone(),
+        from_system_name,
+        to_system_name,
+        &options,
+        risk_model,
+    )
+    .await
+    .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+
+    let (Some(shortest), Some(safest)) = (shortest, safest) else {
+        return Ok(error_reply(ErrorCode::NoRoute, "route not found"));
+    };
+    let shortest = ComparedRoute {
+        risk: route_risk_summary(graph.clone(), &shortest, risk_model).await?,
+        route: shortest,
+    };
+    let safest = ComparedRoute {
+        risk: route_risk_summary(graph, &safest, risk_model).await?,
+        route: safest,
+    };
+    let comparison = RouteComparison {
+        extra_jumps: safest.risk.jumps as i64 - shortest.risk.jumps as i64,
+        catch_probability_reduction: shortest.risk.catch_probability
+            - safest.risk.catch_probability,
+        shortest,
+        safest,
+    };
+    Ok(warp::reply::json(&comparison).into_response())
+}
+
+async fn fastest_route_to_handler(
+    from_system_name: String,
+    to_system_name: String,
+    details: RouteDetailsQuery,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    let (from_system_name, to_system_name) =
+        resolve_route_systems(graph.clone(), &from_system_name, &to_system_name).await?;
+    let systems = get_all_systems(graph.clone())
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let stargates = get_all_stargates(graph.clone())
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let ship = details.profile().unwrap_or(Ship::Frigate.profile());
+
+    match plan_fastest_route(
+        &systems,
+        &stargates,
+        &from_system_name,
+        &to_system_name,
+        &ship,
+    ) {
+        None => Ok(error_reply(ErrorCode::NoRoute, "route not found")),
+        Some(fastest) => match details.format.export_format() {
+            Some(format) => export_route(graph, &fastest.route, format).await,
+            None => Ok(warp::reply::json(&fastest).into_response()),
+        },
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JumpRouteQuery {
+    hull: Hull,
+    #[serde(default = "default_jump_drive_calibration")]
+    jdc: u8,
+    #[serde(default)]
+    gates: bool,
+    #[serde(default)]
+    fatigue: f64,
+    #[serde(default)]
+    optimize: RouteOptimization,
+}
+
+fn default_jump_drive_calibration() -> u8 {
+    5
+}
+
+async fn jump_route_handler(
+    from_system_name: String,
+    to_system_name: String,
+    params: JumpRouteQuery,
+    json_only: JsonOnlyQuery,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    if let Some(refusal) = json_only.refusal() {
+        return Ok(refusal);
+    }
+    if !(0.0..=jump_drive::MAX_FATIGUE_MINUTES).contains(&params.fatigue) {
+        return Ok(error_reply(
+            ErrorCode::InvalidRequest,
+            format!(
+                "fatigue must be between 0 and {} minutes",
+                jump_drive::MAX_FATIGUE_MINUTES
+            ),
+        ));
+    }
+    let (from_system_name, to_system_name) =
+        resolve_route_systems(graph.clone(), &from_system_name, &to_system_name).await?;
+    let systems = get_all_systems(graph.clone())
         .await
-        .map_err(|e| warp::reject::custom(ApiError(e.into())))?
-    {
-        None => {
-            let mut res = warp::reply::json(&serde_json::json!({ "error": "route not found" }))
-                .into_response();
-            *res.status_mut() = StatusCode::NOT_FOUND;
-            Ok(res)
-        }
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let connections = get_stargate_connections(graph)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let options = JumpPlanOptions {
+        hull: params.hull,
+        jump_drive_calibration: params.jdc,
+        use_stargates: params.gates,
+        starting_fatigue_minutes: params.fatigue,
+        optimization: params.optimize,
+    };
+
+    match jump_drive::plan_route(
+        &systems,
+        &connections,
+        &from_system_name,
+        &to_system_name,
+        &options,
+    ) {
+        None => Ok(error_reply(ErrorCode::NoRoute, "route not found")),
         Some(route) => Ok(warp::reply::json(&route).into_response()),
     }
 }
@@ -212,15 +1031,60 @@ async fn wormholes_refresh_handler(
 }
 
 async fn systems_risk_handler(client: Client, graph: Arc<Graph>) -> Result<impl Reply, Rejection> {
-    refresh_jump_risks(client, graph.clone())
+    refresh_jump_risks(client.clone(), graph.clone())
         .await
         .map_err(|e| warp::reject::custom(ApiError(e)))?;
-    refresh_jump_risk_graph(graph)
+    refresh_jump_risk_graph(graph.clone())
         .await
         .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    evaluate_saved_routes(client, graph)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e)))?;
     Ok(reply())
 }
 
+async fn saved_routes_list_handler(graph: Arc<Graph>) -> Result<impl Reply, Rejection> {
+    let saved_routes = get_saved_routes(graph)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    Ok(warp::reply::json(&saved_routes))
+}
+
+async fn saved_routes_add_handler(
+    saved_route: SavedRoute,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    // The route is searched for by exact name when it's evaluated, so store the canonical names.
+    let (from_system_name, to_system_name) = resolve_route_systems(
+        graph.clone(),
+        &saved_route.from_system_name,
+        &saved_route.to_system_name,
+    )
+    .await?;
+    let saved_route = SavedRoute {
+        from_system_name,
+        to_system_name,
+        ..saved_route
+    };
+    save_saved_route(graph, &saved_route)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    Ok(reply::with_status(reply(), StatusCode::CREATED))
+}
+
+async fn saved_routes_remove_handler(
+    name: String,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    let removed = remove_saved_route(graph, name)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    if !removed {
+        return Ok(error_reply(ErrorCode::NotFound, "saved route not found"));
+    }
+    Ok(reply::with_status(reply(), StatusCode::NO_CONTENT).into_response())
+}
+
 async fn systems_refresh_handler(
     client: Client,
     graph: Arc<Graph>,
@@ -243,3 +1107,658 @@ async fn stargates_refresh_handler(
         .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
     Ok(reply())
 }
+
+#[derive(Debug, Deserialize)]
+struct WithinQuery {
+    security: Option<SecurityBand>,
+    region: Option<String>,
+    max_risk: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct SystemInRange {
+    system_id: i64,
+    name: String,
+    security_status: f64,
+    region_name: Option<String>,
+    kills: u32,
+    pod_kills: u32,
+    npc_kills: u32,
+    risk: Option<f64>,
+    jumps: u32,
+}
+
+async fn systems_within_handler(
+    system_name: String,
+    max_jumps: u32,
+    params: WithinQuery,
+    options: RouteOptions,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    let system_name = resolve_system_name(graph.clone(), &system_name).await?;
+    let filter = SystemFilter {
+        security: params.security,
+        region: params.region,
+        max_risk: params.max_risk,
+    };
+    let systems = find_systems_within_jumps(graph, system_name, max_jumps, &options)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    if systems.is_empty() {
+        return Ok(error_reply(ErrorCode::NotFound, "system not found"));
+    }
+
+    let in_range: Vec<SystemInRange> = systems
+        .into_iter()
+        .filter(|(system, _)| filter.matches(system))
+        .map(|(system, jumps)| SystemInRange {
+            system_id: system.system_id,
+            name: system.name,
+            security_status: system.security_status,
+            region_name: system.region_name,
+            kills: system.kills,
+            pod_kills: system.pod_kills,
+            npc_kills: system.npc_kills,
+            risk: system.risk,
+            jumps,
+        })
+        .collect();
+    Ok(warp::reply::json(&in_range).into_response())
+}
+
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+#[derive(Debug, Deserialize)]
+struct SystemSearchQuery {
+    q: String,
+    limit: Option<usize>,
+}
+
+async fn systems_search_handler(
+    params: SystemSearchQuery,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    let systems = get_system_names(graph)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let matches = search_systems(
+        &systems,
+        &params.q,
+        params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+    );
+    Ok(warp::reply::json(&matches))
+}
+
+#[derive(Debug, Serialize)]
+struct SystemDetails {
+    #[serde(flatten)]
+    system: System,
+    neighbors: Vec<Neighbor>,
+}
+
+/// Looks a system up by ID, or by name ignoring case.
+async fn system_details_handler(
+    id_or_name: String,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    let system = match id_or_name.parse::<i64>() {
+        Ok(system_id) => get_system(graph.clone(), system_id).await,
+        Err(_) => find_system_by_name(graph.clone(), &id_or_name).await,
+    }
+    .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let Some(system) = system else {
+        return Ok(error_reply(ErrorCode::NotFound, "system not found"));
+    };
+    let neighbors = get_system_neighbors(graph, system.system_id)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    Ok(warp::reply::json(&SystemDetails { system, neighbors }).into_response())
+}
+
+/// The main trade hubs of high-sec.
+const TRADE_HUBS: [&str; 5] = ["Jita", "Amarr", "Dodixie", "Rens", "Hek"];
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SystemSet {
+    TradeHubs,
+}
+
+#[derive(Debug, Deserialize)]
+struct NearestQuery {
+    security: Option<SecurityBand>,
+    max_kills: Option<u32>,
+    /// A comma separated list of system names.
+    names: Option<String>,
+    set: Option<SystemSet>,
+    wormhole_to: Option<String>,
+}
+
+async fn systems_nearest_handler(
+    system_name: String,
+    params: NearestQuery,
+    options: RouteOptions,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    let system_name = resolve_system_name(graph.clone(), &system_name).await?;
+    let names = match (params.names, params.set) {
+        (Some(names), _) => {
+            let inputs: Vec<String> = names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(String::from)
+                .collect();
+            let systems = get_system_names(graph.clone())
+                .await
+                .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+            Some(resolve_each_system(&systems, &inputs)?)
+        }
+        (None, Some(SystemSet::TradeHubs)) => Some(TRADE_HUBS.map(String::from).to_vec()),
+        (None, None) => None,
+    };
+    let wormhole_to = match params.wormhole_to {
+        Some(wormhole_to) => Some(resolve_system_name(graph.clone(), &wormhole_to).await?),
+        None => None,
+    };
+    let criteria = SystemCriteria {
+        security: params.security,
+        max_kills: params.max_kills,
+        names,
+        wormhole_to,
+    };
+
+    match find_nearest_system(graph, system_name, &criteria, &options)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?
+    {
+        None => Ok(error_reply(ErrorCode::NotFound, "no matching system found")),
+        Some(nearest) => Ok(warp::reply::json(&nearest).into_response()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RouteMatrixRequest {
+    origins: Vec<String>,
+    destinations: Vec<String>,
+    /// Also measure the risk of the safest route between each pair.
+    #[serde(default)]
+    risk: bool,
+    #[serde(default, flatten)]
+    options: RouteOptions,
+}
+
+/// Rows follow the origins and columns the destinations. Pairs with no route are null.
+#[derive(Debug, Serialize)]
+struct RouteMatrix {
+    origins: Vec<String>,
+    destinations: Vec<String>,
+    jumps: Vec<Vec<Option<u32>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    risk: Option<Vec<Vec<Option<f64>>>>,
+}
+
+/// How many route searches requests may run on the graph at once, across all requests.
+const MAX_CONCURRENT_ROUTE_SEARCHES: usize = 8;
+
+static ROUTE_SEARCHES: Semaphore = Semaphore::const_new(MAX_CONCURRENT_ROUTE_SEARCHES);
+
+/// Computes one row of the matrix per origin, running the searches for the origins concurrently,
+/// a few at a time.
+async fn route_matrix_rows(
+    graph: Arc<Graph>,
+    request: &RouteMatrixRequest,
+    weight: RouteWeight,
+) -> Result<Vec<Vec<Option<f64>>>, Rejection> {
+    let mut set = JoinSet::new();
+    for (row, origin) in request.origins.iter().enumerate() {
+        let graph = graph.clone();
+        let origin = origin.clone();
+        let destinations = request.destinations.clone();
+        let options = request.options.clone();
+        set.spawn(async move {
+            let _permit = ROUTE_SEARCHES.acquire().await.unwrap();
+            let costs =
+                get_route_costs(graph, origin, destinations.clone(), weight, &options).await?;
+            let row_costs: Vec<Option<f64>> = destinations
+                .iter()
+                .map(|destination| costs.get(destination).copied())
+                .collect();
+            Ok::<_, Error>((row, row_costs))
+        });
+    }
+
+    let mut rows = vec![Vec::new(); request.origins.len()];
+    while let Some(joined) = set.join_next().await {
+        let (row, row_costs) = joined
+            .map_err(|e| warp::reject::custom(ApiError(e.into())))?
+            .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+        rows[row] = row_costs;
+    }
+    Ok(rows)
+}
+
+async fn route_matrix_handler(
+    request: RouteMatrixRequest,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    let systems = get_system_names(graph.clone())
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let request = RouteMatrixRequest {
+        origins: resolve_each_system(&systems, &request.origins)?,
+        destinations: resolve_each_system(&systems, &request.destinations)?,
+        ..request
+    };
+    let jumps = route_matrix_rows(graph.clone(), &request, RouteWeight::Jumps)
+        .await?
+        .into_iter()
+        .map(|row| row.into_iter().map(|cost| cost.map(|c| c as u32)).collect())
+        .collect();
+
+    let risk = if request.risk {
+        let exists = graph_exists(&graph, String::from("jump-risk"))
+            .await
+            .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+        if !exists {
+            build_jump_risk_graph(graph.clone())
+                .await
+                .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+        }
+        let weight = RouteWeight::Risk(RiskModelKind::configured());
+        Some(route_matrix_rows(graph, &request, weight).await?)
+    } else {
+        None
+    };
+
+    Ok(warp::reply::json(&RouteMatrix {
+        origins: request.origins,
+        destinations: request.destinations,
+        jumps,
+        risk,
+    }))
+}
+
+/// Answers every route in the batch, with one search per source system, mode and options, running
+/// the searches concurrently.
+async fn route_batch_handler(
+    request: RouteBatchRequest,
+    json_only: JsonOnlyQuery,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    if let Some(refusal) = json_only.refusal() {
+        return Ok(refusal);
+    }
+    if request.routes.len() > MAX_BATCH_ROUTES {
+        return Ok(error_reply(
+            ErrorCode::InvalidRequest,
+            format!("a batch may have at most {MAX_BATCH_ROUTES} routes"),
+        ));
+    }
+
+    let systems = get_system_names(graph.clone())
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let mut results: Vec<RouteBatchResult> = request
+        .routes
+        .iter()
+        .map(|item| RouteBatchResult {
+            from: item.from.clone(),
+            to: item.to.clone(),
+            route: None,
+            jumps: None,
+            error: None,
+        })
+        .collect();
+    let mut resolved = Vec::new();
+    for (position, item) in request.routes.iter().enumerate() {
+        let ends = resolve_system(&systems, &item.from)
+            .and_then(|from| Ok((from, resolve_system(&systems, &item.to)?)));
+        match ends {
+            Ok((from, to)) => resolved.push((position, from, to, item)),
+            Err(unresolved) => results[position].error = Some(unresolved_error(&unresolved)),
+        }
+    }
+    let groups = group_by_source(resolved);
+
+    if groups.iter().any(|group| group.mode == RouteMode::Safest) {
+        let exists = graph_exists(&graph, String::from("jump-risk"))
+            .await
+            .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+        if !exists {
+            build_jump_risk_graph(graph.clone())
+                .await
+                .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+        }
+    }
+
+    let risk_model = RiskModelKind::configured();
+    let mut set = JoinSet::new();
+    let mut group_by_task = HashMap::new();
+    for (index, group) in groups.iter().enumerate() {
+        let graph = graph.clone();
+        let group = group.clone();
+        let task = set.spawn(async move {
+            let _permit = ROUTE_SEARCHES.acquire().await.unwrap();
+            find_routes_from(
+                graph,
+                group.from,
+                group.destinations,
+                group.mode.weight(risk_model),
+                &group.options,
+            )
+            .await
+        });
+        group_by_task.insert(task.id(), index);
+    }
+
+    // A search that fails only fails the routes it was answering.
+    while let Some(joined) = set.join_next_with_id().await {
+        let (index, routes) = match joined {
+            Ok((id, routes)) => (group_by_task[&id], routes.map_err(sync::Error::from)),
+            Err(e) => (group_by_task[&e.id()], Err(sync::Error::from(e))),
+        };
+        let group = &groups[index];
+        let routes = match routes {
+            Ok(routes) => routes,
+            Err(e) => {
+                let body = ErrorBody::new(ErrorCode::from(&e), e.to_string());
+                error!("Batch route error ({}): {:?}", body.request_id, e);
+                for (position, _) in &group.routes {
+                    results[*position].error = Some(body.clone());
+                }
+                continue;
+            }
+        };
+        for (position, to) in &group.routes {
+            let position = *position;
+            let route = if &group.from == to {
+                Some(vec![to.clone()])
+            } else {
+                routes.get(to).map(|route| route.systems.clone())
+            };
+            match route {
+                Some(route) => {
+                    results[position].jumps = Some(route.len().saturating_sub(1));
+                    results[position].route = Some(route);
+                }
+                None => {
+                    results[position].error =
+                        Some(ErrorBody::new(ErrorCode::NoRoute, "route not found"));
+                }
+            }
+        }
+    }
+
+    Ok(warp::reply::json(&results).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+struct RallyPointRequest {
+    /// The system each fleet member is currently in.
+    members: Vec<String>,
+    #[serde(default)]
+    objective: RallyObjective,
+    #[serde(default)]
+    avoid: Vec<String>,
+    security: Option<Vec<SecurityBand>>,
+    #[serde(default, flatten)]
+    options: RouteOptions,
+}
+
+async fn rally_point_handler(
+    request: RallyPointRequest,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    let systems = get_all_systems(graph.clone())
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let connections = get_system_connections(graph, &request.options)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let names: Vec<(i64, String)> = systems
+        .iter()
+        .map(|system| (system.system_id, system.name.clone()))
+        .collect();
+    let members = resolve_each_system(&names, &request.members)?;
+    let options = RallyOptions {
+        objective: request.objective,
+        avoid: resolve_each_system(&names, &request.avoid)?,
+        security: request.security,
+    };
+
+    match rally::find_rally_point(&systems, &connections, &members, &options) {
+        None => Ok(error_reply(ErrorCode::NotFound, "no rally point found")),
+        Some(rally_point) => Ok(warp::reply::json(&rally_point).into_response()),
+    }
+}
+
+/// Reprojects both route graphs so they pick up changes to the jump bridges.
+async fn refresh_route_graphs(graph: Arc<Graph>) -> Result<(), Rejection> {
+    refresh_jump_cost_graph(graph.clone())
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    refresh_jump_risk_graph(graph)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))
+}
+
+async fn jump_bridges_list_handler(graph: Arc<Graph>) -> Result<impl Reply, Rejection> {
+    let bridges = get_jump_bridges(graph)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    Ok(warp::reply::json(&bridges))
+}
+
+async fn jump_bridges_add_handler(
+    bridge: JumpBridge,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    let (from_system_name, to_system_name) = resolve_route_systems(
+        graph.clone(),
+        &bridge.from_system_name,
+        &bridge.to_system_name,
+    )
+    .await?;
+    let bridge = JumpBridge {
+        from_system_name,
+        to_system_name,
+        ..bridge
+    };
+    let saved = save_jump_bridge(graph.clone(), &bridge)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    if !saved {
+        return Ok(error_reply(ErrorCode::UnknownSystem, "system not found"));
+    }
+    refresh_route_graphs(graph).await?;
+    Ok(reply::with_status(reply(), StatusCode::CREATED).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+struct JumpBridgeImportQuery {
+    owner: Option<String>,
+}
+
+async fn jump_bridges_import_handler(
+    params: JumpBridgeImportQuery,
+    body: warp::hyper::body::Bytes,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    let text = String::from_utf8_lossy(&body);
+    let bridges = match parse_jump_bridges(&text, params.owner.as_deref()) {
+        Ok(bridges) => bridges,
+        Err(e) => {
+            return Ok(error_reply(ErrorCode::InvalidRequest, e.to_string()));
+        }
+    };
+    // Every end is resolved before anything is saved, so a typo doesn't leave half an import.
+    let systems = get_system_names(graph.clone())
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    let resolve = |input: &str| {
+        resolve_system(&systems, input).map_err(|e| warp::reject::custom(UnknownSystem(e)))
+    };
+    let bridges = bridges
+        .into_iter()
+        .map(|bridge| {
+            Ok(JumpBridge {
+                from_system_name: resolve(&bridge.from_system_name)?,
+                to_system_name: resolve(&bridge.to_system_name)?,
+                ..bridge
+            })
+        })
+        .collect::<Result<Vec<_>, Rejection>>()?;
+
+    let mut imported = 0;
+    let mut unknown = Vec::new();
+    for bridge in bridges {
+        let saved = save_jump_bridge(graph.clone(), &bridge)
+            .await
+            .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+        if saved {
+            imported += 1;
+        } else {
+            unknown.push(format!(
+                "{} » {}",
+                bridge.from_system_name, bridge.to_system_name
+            ));
+        }
+    }
+    refresh_route_graphs(graph).await?;
+
+    Ok(
+        warp::reply::json(&serde_json::json!({ "imported": imported, "unknown": unknown }))
+            .into_response(),
+    )
+}
+
+async fn jump_bridges_remove_handler(
+    from_system_name: String,
+    to_system_name: String,
+    graph: Arc<Graph>,
+) -> Result<impl Reply, Rejection> {
+    let (from_system_name, to_system_name) =
+        resolve_route_systems(graph.clone(), &from_system_name, &to_system_name).await?;
+    let removed = remove_jump_bridge(graph.clone(), from_system_name, to_system_name)
+        .await
+        .map_err(|e| warp::reject::custom(ApiError(e.into())))?;
+    if !removed {
+        return Ok(error_reply(ErrorCode::NotFound, "jump bridge not found"));
+    }
+    refresh_route_graphs(graph).await?;
+    Ok(reply().into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUTE_OPTIONS: [&str; 3] = ["bridges", "wormholes", "avoid_gates"];
+    const ROUTE_DETAILS: [&str; 6] = ["ship", "warp_speed", "align_time", "legs", "risk", "format"];
+    const RISK_QUERY: [&str; 4] = [
+        "risk_model",
+        "departure_hour",
+        "hours_after_downtime",
+        "avoid_camps",
+    ];
+
+    /// The query parameters each filter reads, by path and method.
+    fn filter_queries() -> Vec<(&'static str, &'static str, Vec<&'static str>)> {
+        vec![
+            (
+                "/shortest-route/{from}/to/{to}",
+                "get",
+                [&ROUTE_OPTIONS[..], &ROUTE_DETAILS].concat(),
+            ),
+            (
+                "/safest-route/{from}/to/{to}",
+                "get",
+                [&ROUTE_OPTIONS[..], &ROUTE_DETAILS, &RISK_QUERY].concat(),
+            ),
+            (
+                "/route-comparison/{from}/to/{to}",
+                "get",
+                [&ROUTE_OPTIONS[..], &["risk_model", "format"]].concat(),
+            ),
+            (
+                "/fastest-route/{from}/to/{to}",
+                "get",
+                ROUTE_DETAILS.to_vec(),
+            ),
+            (
+                "/jump-route/{from}/to/{to}",
+                "get",
+                vec!["hull", "jdc", "gates", "fatigue", "optimize", "format"],
+            ),
+            ("/routes/batch", "post", vec!["format"]),
+            (
+                "/systems/{system}/within/{jumps}",
+                "get",
+                [&["security", "region", "max_risk"][..], &ROUTE_OPTIONS].concat(),
+            ),
+            (
+                "/systems/{system}/nearest",
+                "get",
+                [
+                    &["security", "max_kills", "names", "set", "wormhole_to"][..],
+                    &ROUTE_OPTIONS,
+                ]
+                .concat(),
+            ),
+            ("/systems/search", "get", vec!["q", "limit"]),
+            ("/jump-bridges/import", "post", vec!["owner"]),
+            (
+                "/alerts/camps",
+                "get",
+                vec!["window_minutes", "min_kills", "active_minutes"],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_openapi_documents_the_query_every_filter_reads() {
+        let document = openapi::document();
+        let queries = filter_queries();
+        for (path, operations) in document["paths"].as_object().unwrap() {
+            for (method, operation) in operations.as_object().unwrap() {
+                let mut documented: Vec<&str> = operation["parameters"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|parameter| parameter["in"] == "query")
+                    .map(|parameter| parameter["name"].as_str().unwrap())
+                    .collect();
+                let mut read = queries
+                    .iter()
+                    .find(|(p, m, _)| p == path && m == method)
+                    .map(|(_, _, names)| names.clone())
+                    .unwrap_or_default();
+                documented.sort_unstable();
+                read.sort_unstable();
+                assert_eq!(documented, read, "{method} {path}");
+            }
+        }
+        for (path, method, _) in &queries {
+            assert!(
+                document["paths"][path][method].is_object(),
+                "{method} {path}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_nearest_query_reads_every_documented_filter() {
+        let query = warp::test::request()
+            .path("/?security=low&max_kills=5&names=jita,amarr&set=trade_hubs&wormhole_to=Thera")
+            .filter(&warp::query::<NearestQuery>())
+            .await
+            .unwrap();
+
+        assert_eq!(query.security, Some(SecurityBand::Low));
+        assert_eq!(query.max_kills, Some(5));
+        assert_eq!(query.names.as_deref(), Some("jita,amarr"));
+        assert!(matches!(query.set, Some(SystemSet::TradeHubs)));
+        assert_eq!(query.wormhole_to.as_deref(), Some("Thera"));
+    }
+}
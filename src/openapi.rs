@@ -0,0 +1,1151 @@
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use crate::alerts::WebhookFormat;
+use crate::api_error::ErrorCode;
+use crate::database::{ConnectionKind, GateType, RouteMode, SecurityBand};
+use crate::events::SyncJob;
+use crate::jump_drive::{Hull, LegKind, RouteOptimization};
+use crate::rally::RallyObjective;
+use crate::risk::RiskModelKind;
+use crate::route_export::RouteFormat;
+use crate::search::MatchKind;
+use crate::travel_time::Ship;
+
+/// A page rendering the OpenAPI document with Swagger UI. The version is pinned so the page
+/// doesn't change under us when a new release is published.
+pub const DOCS_PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>eve-graph API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5.17.14/swagger-ui.css">
+</head>
+<body>
+  <div id="docs"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5.17.14/swagger-ui-bundle.js"></script>
+  <script>SwaggerUIBundle({ url: "/openapi.json", dom_id: "#docs" });</script>
+</body>
+</html>
+"##;
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+fn array_of(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn string_enum<T: Serialize>(values: &[T]) -> Value {
+    json!({ "type": "string", "enum": values })
+}
+
+fn path_parameter(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": { "type": "string" },
+    })
+}
+
+fn query_parameter(name: &str, schema: Value, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": false,
+        "description": description,
+        "schema": schema,
+    })
+}
+
+fn required(mut parameter: Value) -> Value {
+    parameter["required"] = json!(true);
+    parameter
+}
+
+fn route_ends() -> Vec<Value> {
+    vec![
+        path_parameter(
+            "from",
+            "The system to start from, by name ignoring case or by ID.",
+        ),
+        path_parameter("to", "The system to go to, by name ignoring case or by ID."),
+    ]
+}
+
+fn route_options() -> Vec<Value> {
+    vec![
+        query_parameter(
+            "bridges",
+            json!({ "type": "boolean", "default": false }),
+            "Use registered jump bridges.",
+        ),
+        query_parameter(
+            "wormholes",
+            json!({ "type": "boolean", "default": true }),
+            "Use the wormholes reported by EVE Scout.",
+        ),
+        query_parameter(
+            "avoid_gates",
+            json!({ "type": "string", "example": "region,constellation" }),
+            "A comma separated list of stargate types not to use: system, constellation, region.",
+        ),
+    ]
+}
+
+fn route_details() -> Vec<Value> {
+    vec![
+        query_parameter(
+            "ship",
+            string_enum(&Ship::ALL),
+            "Adds the estimated travel time for this ship.",
+        ),
+        query_parameter(
+            "warp_speed",
+            json!({ "type": "number" }),
+            "Overrides the ship's warp speed, in AU/s.",
+        ),
+        query_parameter(
+            "align_time",
+            json!({ "type": "number" }),
+            "Overrides the ship's align time, in seconds.",
+        ),
+        query_parameter(
+            "legs",
+            json!({ "type": "boolean", "default": false }),
+            "Adds how to take every hop, including the stargate to use.",
+        ),
+        query_parameter(
+            "risk",
+            json!({ "type": "boolean", "default": false }),
+            "Adds a summary of the route's risk.",
+        ),
+        query_parameter(
+            "format",
+            json!({
+                "type": "string",
+                "enum": RouteFormat::ALL.map(|format| format.as_str()),
+                "default": "json",
+            }),
+            "Renders the route as EVE chat links, autopilot waypoint system IDs or a DOTLAN URL \
+             instead of JSON. Other details are left out.",
+        ),
+    ]
+}
+
+/// The `format` of an endpoint that only answers with JSON, where any other format is refused.
+fn json_format() -> Value {
+    query_parameter(
+        "format",
+        string_enum(&[RouteFormat::Json.as_str()]),
+        "Only json is supported here. Other formats are refused with invalid_request.",
+    )
+}
+
+fn risk_model() -> Value {
+    query_parameter(
+        "risk_model",
+        schema_ref("RiskModel"),
+        "The risk model to weigh jumps by, instead of the configured one.",
+    )
+}
+
+fn risk_query() -> Vec<Value> {
+    vec![
+        risk_model(),
+        query_parameter(
+            "departure_hour",
+            json!({ "type": "integer", "minimum": 0, "maximum": 23 }),
+            "Weighs the route by the activity usually seen at this hour (EVE time).",
+        ),
+        query_parameter(
+            "hours_after_downtime",
+            json!({ "type": "integer", "minimum": 0 }),
+            "Weighs the route by the activity usually seen this many hours after downtime.",
+        ),
+        query_parameter(
+            "avoid_camps",
+            json!({ "type": "boolean", "default": false }),
+            "Keeps the route out of systems with an active gate camp where possible.",
+        ),
+    ]
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+/// An operation answering with JSON, and with an error body on failure.
+fn operation(
+    operation_id: &str,
+    summary: &str,
+    tag: &str,
+    parameters: Vec<Value>,
+    response: Value,
+) -> Value {
+    json!({
+        "operationId": operation_id,
+        "summary": summary,
+        "tags": [tag],
+        "parameters": parameters,
+        "responses": {
+            "200": { "description": "OK", "content": json_content(response) },
+            "default": { "$ref": "#/components/responses/Error" },
+        },
+    })
+}
+
+/// An operation with no body in its response.
+fn empty_operation(operation_id: &str, summary: &str, tag: &str, status: &str) -> Value {
+    json!({
+        "operationId": operation_id,
+        "summary": summary,
+        "tags": [tag],
+        "responses": {
+            status: { "description": "Done" },
+            "default": { "$ref": "#/components/responses/Error" },
+        },
+    })
+}
+
+fn with_body(mut operation: Value, schema: Value) -> Value {
+    operation["requestBody"] = json!({ "required": true, "content": json_content(schema) });
+    operation
+}
+
+fn route_response() -> Value {
+    json!({
+        "oneOf": [schema_ref("Route"), schema_ref("RouteResponse")],
+        "description": "The route, or the route with its details when any were asked for.",
+    })
+}
+
+fn paths() -> Value {
+    let mut paths = Map::new();
+    let mut add = |path: &str, method: &str, operation: Value| {
+        let item = paths.entry(path).or_insert_with(|| json!({}));
+        item[method] = operation;
+    };
+
+    add(
+        "/shortest-route/{from}/to/{to}",
+        "get",
+        operation(
+            "shortestRoute",
+            "The route with the fewest jumps",
+            "routes",
+            [route_ends(), route_options(), route_details()].concat(),
+            route_response(),
+        ),
+    );
+    add(
+        "/safest-route/{from}/to/{to}",
+        "get",
+        operation(
+            "safestRoute",
+            "The route with the least risk",
+            "routes",
+            [route_ends(), route_options(), route_details(), risk_query()].concat(),
+            route_response(),
+        ),
+    );
+    add(
+        "/route-comparison/{from}/to/{to}",
+        "get",
+        operation(
+            "compareRoutes",
+            "The shortest and safest routes with their risk",
+            "routes",
+            [
+                route_ends(),
+                route_options(),
+                vec![risk_model(), json_format()],
+            ]
+            .concat(),
+            schema_ref("RouteComparison"),
+        ),
+    );
+    add(
+        "/fastest-route/{from}/to/{to}",
+        "get",
+        operation(
+            "fastestRoute",
+            "The route that takes the least time to fly",
+            "routes",
+            [route_ends(), route_details()].concat(),
+            schema_ref("FastestRoute"),
+        ),
+    );
+    add(
+        "/jump-route/{from}/to/{to}",
+        "get",
+        operation(
+            "jumpRoute",
+            "A capital jump drive route",
+            "routes",
+            [
+                route_ends(),
+                vec![
+                    required(query_parameter(
+                        "hull",
+                        string_enum(&Hull::ALL),
+                        "The hull to plan for.",
+                    )),
+                    query_parameter(
+                        "jdc",
+                        json!({ "type": "integer", "minimum": 0, "maximum": 5, "default": 5 }),
+                        "The Jump Drive Calibration skill level.",
+                    ),
+                    query_parameter(
+                        "gates",
+                        json!({ "type": "boolean", "default": false }),
+                        "Also take stargates.",
+                    ),
+                    query_parameter(
+                        "fatigue",
+                        json!({ "type": "number", "minimum": 0, "maximum": 300, "default": 0 }),
+                        "Jump fatigue at the start, in minutes.",
+                    ),
+                    query_parameter(
+                        "optimize",
+                        string_enum(&RouteOptimization::ALL),
+                        "What to minimize.",
+                    ),
+                    json_format(),
+                ],
+            ]
+            .concat(),
+            schema_ref("JumpRoute"),
+        ),
+    );
+    add(
+        "/route-matrix",
+        "post",
+        with_body(
+            operation(
+                "routeMatrix",
+                "The jumps between every origin and destination",
+                "routes",
+                vec![],
+                schema_ref("RouteMatrix"),
+            ),
+            schema_ref("RouteMatrixRequest"),
+        ),
+    );
+    add(
+        "/routes/batch",
+        "post",
+        with_body(
+            operation(
+                "routeBatch",
+                "Many routes at once, in the order they were asked for",
+                "routes",
+                vec![json_format()],
+                array_of(schema_ref("RouteBatchResult")),
+            ),
+            schema_ref("RouteBatchRequest"),
+        ),
+    );
+    add(
+        "/rally-point",
+        "post",
+        with_body(
+            operation(
+                "rallyPoint",
+                "The best system for a fleet to meet in",
+                "routes",
+                vec![],
+                schema_ref("RallyPoint"),
+            ),
+            schema_ref("RallyPointRequest"),
+        ),
+    );
+
+    add(
+        "/systems/search",
+        "get",
+        operation(
+            "searchSystems",
+            "Systems whose names match a search",
+            "systems",
+            vec![
+                required(query_parameter(
+                    "q",
+                    json!({ "type": "string" }),
+                    "The name or start of the name, ignoring case and tolerating typos.",
+                )),
+                query_parameter(
+                    "limit",
+                    json!({ "type": "integer", "minimum": 0, "default": 10 }),
+                    "The most matches to return.",
+                ),
+            ],
+            array_of(schema_ref("SystemMatch")),
+        ),
+    );
+    add(
+        "/systems/{system}",
+        "get",
+        operation(
+            "getSystem",
+            "Everything known about a system",
+            "systems",
+            vec![path_parameter(
+                "system",
+                "The system, by name ignoring case or by ID.",
+            )],
+            schema_ref("SystemDetails"),
+        ),
+    );
+    add(
+        "/systems/{system}/within/{jumps}",
+        "get",
+        operation(
+            "systemsWithin",
+            "The systems within a number of jumps",
+            "systems",
+            vec![
+                path_parameter("system", "The system to start from."),
+                json!({
+                    "name": "jumps",
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "integer", "minimum": 0 },
+                }),
+                query_parameter(
+                    "security",
+                    schema_ref("SecurityBand"),
+                    "Only systems of this security.",
+                ),
+                query_parameter(
+                    "region",
+                    json!({ "type": "string" }),
+                    "Only systems in this region.",
+                ),
+                query_parameter(
+                    "max_risk",
+                    json!({ "type": "number" }),
+                    "Only systems at most this risky.",
+                ),
+            ]
+            .into_iter()
+            .chain(route_options())
+            .collect(),
+            array_of(schema_ref("SystemInRange")),
+        ),
+    );
+    add(
+        "/systems/{system}/nearest",
+        "get",
+        operation(
+            "nearestSystem",
+            "The closest system matching some criteria",
+            "systems",
+            vec![
+                path_parameter("system", "The system to start from."),
+                query_parameter(
+                    "security",
+                    schema_ref("SecurityBand"),
+                    "Only systems of this security.",
+                ),
+                query_parameter(
+                    "max_kills",
+                    json!({ "type": "integer" }),
+                    "Only systems with at most this many kills.",
+                ),
+                query_parameter(
+                    "names",
+                    json!({ "type": "string" }),
+                    "A comma separated list of systems to pick from.",
+                ),
+                query_parameter(
+                    "set",
+                    string_enum(&["trade_hubs"]),
+                    "A known set of systems to pick from.",
+                ),
+                query_parameter(
+                    "wormhole_to",
+                    json!({ "type": "string" }),
+                    "Only systems with a wormhole to this system.",
+                ),
+            ]
+            .into_iter()
+            .chain(route_options())
+            .collect(),
+            schema_ref("NearestSystem"),
+        ),
+    );
+
+    add(
+        "/alerts/camps",
+        "get",
+        operation(
+            "campAlerts",
+            "Systems with an active gate camp",
+            "alerts",
+            vec![
+                query_parameter(
+                    "window_minutes",
+                    json!({ "type": "integer", "minimum": 1, "default": 10 }),
+                    "Kills this close together belong to the same burst.",
+                ),
+                query_parameter(
+                    "min_kills",
+                    json!({ "type": "integer", "default": 3 }),
+                    "The fewest kills that make a camp.",
+                ),
+                query_parameter(
+                    "active_minutes",
+                    json!({ "type": "integer", "default": 45 }),
+                    "How long a camp stays active after its last kill.",
+                ),
+            ],
+            array_of(schema_ref("CampAlert")),
+        ),
+    );
+    add(
+        "/saved-routes",
+        "get",
+        operation(
+            "listSavedRoutes",
+            "The saved routes",
+            "alerts",
+            vec![],
+            array_of(schema_ref("SavedRoute")),
+        ),
+    );
+    add(
+        "/saved-routes",
+        "post",
+        with_body(
+            empty_operation("saveRoute", "Saves a route to watch", "alerts", "201"),
+            schema_ref("SavedRoute"),
+        ),
+    );
+    let mut remove_saved_route =
+        empty_operation("removeSavedRoute", "Removes a saved route", "alerts", "204");
+    remove_saved_route["parameters"] = json!([path_parameter("name", "The saved route's name.")]);
+    add("/saved-routes/{name}", "delete", remove_saved_route);
+
+    add(
+        "/jump-bridges",
+        "get",
+        operation(
+            "listJumpBridges",
+            "The registered jump bridges",
+            "jump bridges",
+            vec![],
+            array_of(schema_ref("JumpBridge")),
+        ),
+    );
+    add(
+        "/jump-bridges",
+        "post",
+        with_body(
+            empty_operation(
+                "addJumpBridge",
+                "Registers a jump bridge",
+                "jump bridges",
+                "201",
+            ),
+            schema_ref("JumpBridge"),
+        ),
+    );
+    let mut import_bridges = operation(
+        "importJumpBridges",
+        "Registers the jump bridges in a pasted list",
+        "jump bridges",
+        vec![query_parameter(
+            "owner",
+            json!({ "type": "string" }),
+            "The owner of every imported bridge.",
+        )],
+        json!({
+            "type": "object",
+            "properties": {
+                "imported": { "type": "integer" },
+                "unknown": array_of(json!({ "type": "string" })),
+            },
+        }),
+    );
+    import_bridges["requestBody"] = json!({ "required": true, "content": { "text/plain": { "schema": { "type": "string" } } } });
+    add("/jump-bridges/import", "post", import_bridges);
+    let mut remove_bridge = empty_operation(
+        "removeJumpBridge",
+        "Removes a jump bridge",
+        "jump bridges",
+        "200",
+    );
+    remove_bridge["parameters"] = json!(route_ends());
+    add("/jump-bridges/{from}/to/{to}", "delete", remove_bridge);
+
+    for (path, operation_id, summary) in [
+        (
+            "/systems/refresh",
+            "refreshSystems",
+            "Synchronizes systems with ESI",
+        ),
+        (
+            "/systems/risk",
+            "refreshRisk",
+            "Refreshes system risks from ESI",
+        ),
+        (
+            "/stargates/refresh",
+            "refreshStargates",
+            "Synchronizes stargates with ESI",
+        ),
+        (
+            "/wormholes/refresh",
+            "refreshWormholes",
+            "Refreshes wormholes from EVE Scout",
+        ),
+    ] {
+        add(
+            path,
+            "post",
+            empty_operation(operation_id, summary, "jobs", "200"),
+        );
+    }
+    add(
+        "/events",
+        "get",
+        json!({
+            "operationId": "graphEvents",
+            "summary": "Server-sent events for changes to the graph",
+            "tags": ["jobs"],
+            "responses": {
+                "200": {
+                    "description": "A stream of events, each named after its type.",
+                    "content": { "text/event-stream": { "schema": schema_ref("GraphEvent") } },
+                },
+            },
+        }),
+    );
+
+    add(
+        "/graphql",
+        "post",
+        json!({
+            "operationId": "graphql",
+            "summary": "Runs a GraphQL query over systems and routes",
+            "tags": ["graphql"],
+            "requestBody": {
+                "required": true,
+                "content": json_content(json!({
+                    "type": "object",
+                    "required": ["query"],
+                    "properties": {
+                        "query": { "type": "string" },
+                        "operationName": { "type": "string" },
+                        "variables": { "type": "object" },
+                    },
+                })),
+            },
+            "responses": {
+                "200": {
+                    "description": "The query's data and any errors, as GraphQL reports them.",
+                    "content": json_content(json!({ "type": "object" })),
+                },
+                "default": { "$ref": "#/components/responses/Error" },
+            },
+        }),
+    );
+    add(
+        "/graphql",
+        "get",
+        json!({
+            "operationId": "graphiql",
+            "summary": "A GraphiQL page for exploring the GraphQL schema",
+            "tags": ["graphql"],
+            "responses": {
+                "200": { "description": "HTML", "content": { "text/html": { "schema": { "type": "string" } } } },
+            },
+        }),
+    );
+
+    Value::Object(paths)
+}
+
+fn schemas() -> Value {
+    let number = json!({ "type": "number" });
+    let integer = json!({ "type": "integer" });
+    let string = json!({ "type": "string" });
+    let boolean = json!({ "type": "boolean" });
+    let names = array_of(string.clone());
+    let nullable = |schema: &Value| {
+        let mut schema = schema.clone();
+        schema["nullable"] = json!(true);
+        schema
+    };
+
+    let mut schemas = Map::new();
+    schemas.insert(
+        String::from("Error"),
+        json!({
+            "type": "object",
+            "required": ["code", "error", "request_id"],
+            "properties": {
+                "code": { "type": "string", "enum": ErrorCode::ALL },
+                "error": string,
+                "request_id": string,
+                "suggestions": names,
+            },
+        }),
+    );
+    schemas.insert(String::from("RiskModel"), string_enum(&RiskModelKind::ALL));
+    schemas.insert(
+        String::from("SecurityBand"),
+        string_enum(&SecurityBand::ALL),
+    );
+    schemas.insert(String::from("GateType"), string_enum(&GateType::ALL));
+    schemas.insert(
+        String::from("ConnectionKind"),
+        string_enum(&ConnectionKind::ALL),
+    );
+    schemas.insert(String::from("Route"), names.clone());
+    schemas.insert(
+        String::from("RouteResponse"),
+        json!({
+            "type": "object",
+            "properties": {
+                "route": names,
+                "travel_time": schema_ref("TravelTime"),
+                "legs": array_of(schema_ref("RouteLeg")),
+                "risk": schema_ref("RouteRiskSummary"),
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("TravelTime"),
+        json!({
+            "type": "object",
+            "properties": {
+                "total_seconds": number,
+                "align_seconds": number,
+                "warp_seconds": number,
+                "gate_seconds": number,
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("RouteLeg"),
+        json!({
+            "type": "object",
+            "properties": {
+                "from": string,
+                "to": string,
+                "kind": schema_ref("ConnectionKind"),
+                "stargate_id": nullable(&integer),
+                "stargate_name": nullable(&string),
+                "gate_type": schema_ref("GateType"),
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("HopRisk"),
+        json!({
+            "type": "object",
+            "properties": {
+                "from": string,
+                "to": string,
+                "risk": number,
+                "catch_probability": number,
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("RouteRiskSummary"),
+        json!({
+            "type": "object",
+            "properties": {
+                "jumps": integer,
+                "total_risk": number,
+                "catch_probability": number,
+                "riskiest_hops": array_of(schema_ref("HopRisk")),
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("ComparedRoute"),
+        json!({
+            "type": "object",
+            "properties": { "route": names, "risk": schema_ref("RouteRiskSummary") },
+        }),
+    );
+    schemas.insert(
+        String::from("RouteComparison"),
+        json!({
+            "type": "object",
+            "properties": {
+                "shortest": schema_ref("ComparedRoute"),
+                "safest": schema_ref("ComparedRoute"),
+                "extra_jumps": integer,
+                "catch_probability_reduction": number,
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("FastestRoute"),
+        json!({
+            "type": "object",
+            "properties": { "route": names, "travel_time": schema_ref("TravelTime") },
+        }),
+    );
+    schemas.insert(
+        String::from("JumpLeg"),
+        json!({
+            "type": "object",
+            "properties": {
+                "from": string,
+                "to": string,
+                "kind": string_enum(&LegKind::ALL),
+                "light_years": number,
+                "departs_at_minutes": number,
+                "fatigue_minutes": number,
+                "reactivation_minutes": number,
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("JumpRoute"),
+        json!({
+            "type": "object",
+            "properties": {
+                "systems": names,
+                "legs": array_of(schema_ref("JumpLeg")),
+                "total_light_years": number,
+                "total_minutes": number,
+                "final_fatigue_minutes": number,
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("RouteMatrixRequest"),
+        json!({
+            "type": "object",
+            "required": ["origins", "destinations"],
+            "properties": {
+                "origins": names,
+                "destinations": names,
+                "risk": boolean,
+                "bridges": boolean,
+                "wormholes": boolean,
+                "avoid_gates": string,
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("RouteMatrix"),
+        json!({
+            "type": "object",
+            "properties": {
+                "origins": names,
+                "destinations": names,
+                "jumps": array_of(array_of(nullable(&integer))),
+                "risk": array_of(array_of(nullable(&number))),
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("RouteBatchRequest"),
+        json!({
+            "type": "object",
+            "required": ["routes"],
+            "properties": {
+                "routes": array_of(json!({
+                    "type": "object",
+                    "required": ["from", "to"],
+                    "properties": {
+                        "from": string,
+                        "to": string,
+                        "mode": string_enum(&RouteMode::ALL),
+                        "bridges": boolean,
+                        "wormholes": boolean,
+                        "avoid_gates": string,
+                    },
+                })),
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("RouteBatchResult"),
+        json!({
+            "type": "object",
+            "required": ["from", "to"],
+            "properties": {
+                "from": string,
+                "to": string,
+                "route": names,
+                "jumps": integer,
+                "error": schema_ref("Error"),
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("RallyPointRequest"),
+        json!({
+            "type": "object",
+            "required": ["members"],
+            "properties": {
+                "members": names,
+                "objective": string_enum(&RallyObjective::ALL),
+                "avoid": names,
+                "security": array_of(schema_ref("SecurityBand")),
+                "bridges": boolean,
+                "wormholes": boolean,
+                "avoid_gates": string,
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("MemberRoute"),
+        json!({
+            "type": "object",
+            "properties": { "from": string, "jumps": integer, "route": names },
+        }),
+    );
+    schemas.insert(
+        String::from("RallyPoint"),
+        json!({
+            "type": "object",
+            "properties": {
+                "system": string,
+                "max_jumps": integer,
+                "total_jumps": integer,
+                "routes": array_of(schema_ref("MemberRoute")),
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("SystemMatch"),
+        json!({
+            "type": "object",
+            "properties": {
+                "system_id": integer,
+                "name": string,
+                "match": string_enum(&MatchKind::ALL),
+                "distance": integer,
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("Neighbor"),
+        json!({
+            "type": "object",
+            "properties": {
+                "system_id": integer,
+                "name": string,
+                "kind": schema_ref("ConnectionKind"),
+                "gate_type": schema_ref("GateType"),
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("SystemDetails"),
+        json!({
+            "type": "object",
+            "properties": {
+                "system_id": integer,
+                "name": string,
+                "security_status": number,
+                "security_class": string,
+                "constellation_id": integer,
+                "constellation_name": nullable(&string),
+                "region_id": nullable(&integer),
+                "region_name": nullable(&string),
+                "x": number,
+                "y": number,
+                "z": number,
+                "star_id": integer,
+                "planets": array_of(integer.clone()),
+                "stargates": array_of(integer.clone()),
+                "kills": integer,
+                "pod_kills": integer,
+                "npc_kills": integer,
+                "jumps": integer,
+                "risk": nullable(&number),
+                "neighbors": array_of(schema_ref("Neighbor")),
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("SystemInRange"),
+        json!({
+            "type": "object",
+            "properties": {
+                "system_id": integer,
+                "name": string,
+                "security_status": number,
+                "region_name": nullable(&string),
+                "kills": integer,
+                "pod_kills": integer,
+                "npc_kills": integer,
+                "risk": nullable(&number),
+                "jumps": integer,
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("NearestSystem"),
+        json!({
+            "type": "object",
+            "properties": { "name": string, "jumps": integer, "route": names },
+        }),
+    );
+    schemas.insert(
+        String::from("CampAlert"),
+        json!({
+            "type": "object",
+            "properties": {
+                "system_name": nullable(&string),
+                "system_id": integer,
+                "stargate_id": nullable(&integer),
+                "stargate_name": nullable(&string),
+                "kills": integer,
+                "pod_kills": integer,
+                "max_attackers": integer,
+                "first_kill": integer,
+                "last_kill": integer,
+                "minutes_since_last_kill": number,
+                "confidence": number,
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("SavedRoute"),
+        json!({
+            "type": "object",
+            "required": ["name", "from_system_name", "to_system_name", "threshold"],
+            "properties": {
+                "name": string,
+                "from_system_name": string,
+                "to_system_name": string,
+                "threshold": number,
+                "webhook_url": { "type": "string", "writeOnly": true },
+                "webhook_format": string_enum(&WebhookFormat::ALL),
+                "route": names,
+                "hot_systems": names,
+            },
+        }),
+    );
+    schemas.insert(
+        String::from("JumpBridge"),
+        json!({
+            "type": "object",
+            "required": ["from_system_name", "to_system_name"],
+            "properties": {
+                "from_system_name": string,
+                "to_system_name": string,
+                "owner": nullable(&string),
+                "structure_id": nullable(&integer),
+            },
+        }),
+    );
+    schemas.insert(String::from("GraphEvent"), json!({
+            "type": "object",
+            "required": ["type"],
+            "properties": {
+                "type": string_enum(&["wormholes_changed", "risk_refreshed", "sync_finished"]),
+                "added": array_of(json!({ "type": "object", "properties": { "from": string, "to": string } })),
+                "removed": array_of(json!({ "type": "object", "properties": { "from": string, "to": string } })),
+                "most_changed": array_of(json!({
+                    "type": "object",
+                    "properties": {
+                        "system_id": integer,
+                        "name": string,
+                        "previous_risk": nullable(&number),
+                        "risk": number,
+                    },
+                })),
+                "job": string_enum(&SyncJob::ALL),
+            },
+        }));
+
+    Value::Object(schemas)
+}
+
+/// The OpenAPI 3 document describing every endpoint.
+pub fn document() -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "eve-graph",
+            "description": "A route finding application for navigating in EVE Online.",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths(),
+        "components": {
+            "schemas": schemas(),
+            "responses": {
+                "Error": {
+                    "description": "An error, with a code to tell failures apart.",
+                    "headers": {
+                        "x-request-id": { "schema": { "type": "string" } },
+                    },
+                    "content": json_content(schema_ref("Error")),
+                },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_refs<'a>(value: &'a Value, refs: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    refs.push(reference);
+                }
+                map.values().for_each(|value| collect_refs(value, refs));
+            }
+            Value::Array(values) => values.iter().for_each(|value| collect_refs(value, refs)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_every_reference_resolves() {
+        let document = document();
+        let mut refs = Vec::new();
+        collect_refs(&document, &mut refs);
+
+        assert!(!refs.is_empty());
+        for reference in refs {
+            let pointer = reference.trim_start_matches('#');
+            assert!(
+                document.pointer(pointer).is_some(),
+                "{reference} doesn't resolve"
+            );
+        }
+    }
+
+    #[test]
+    fn test_path_parameters_are_described() {
+        let document = document();
+        for (path, item) in document["paths"].as_object().unwrap() {
+            let names: Vec<&str> = path
+                .split('/')
+                .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+                .collect();
+            for (method, operation) in item.as_object().unwrap() {
+                for name in &names {
+                    let described = operation["parameters"]
+                        .as_array()
+                        .is_some_and(|parameters| {
+                            parameters.iter().any(|parameter| {
+                                parameter["in"] == "path" && parameter["name"] == *name
+                            })
+                        });
+                    assert!(described, "{method} {path} doesn't describe {{{name}}}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::{SecurityBand, System};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RallyObjective {
+    /// Keep the member furthest away as close as possible.
+    #[default]
+    MaxJumps,
+    /// Keep the combined travel of every member as low as possible.
+    TotalJumps,
+}
+
+impl RallyObjective {
+    pub const ALL: [RallyObjective; 2] = [RallyObjective::MaxJumps, RallyObjective::TotalJumps];
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RallyOptions {
+    pub objective: RallyObjective,
+    /// Systems that can't be travelled through or chosen as the rally point.
+    pub avoid: Vec<String>,
+    /// When set, routes only pass through and end in systems of these security bands. Members can
+    /// always leave the system they're in.
+    pub security: Option<Vec<SecurityBand>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MemberRoute {
+    pub from: String,
+    pub jumps: u32,
+    pub route: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RallyPoint {
+    pub system: String,
+    pub max_jumps: u32,
+    pub total_jumps: u32,
+    pub routes: Vec<MemberRoute>,
+}
+
+/// Jump counts and the previous system on the shortest route from one member to every system.
+struct Reach {
+    jumps: Vec<Option<u32>>,
+    previous: Vec<Option<usize>>,
+}
+
+fn breadth_first_search(neighbors: &[Vec<usize>], allowed: &[bool], source: usize) -> Reach {
+    let mut jumps = vec![None; neighbors.len()];
+    let mut previous = vec![None; neighbors.len()];
+    let mut queue = VecDeque::from([source]);
+    jumps[source] = Some(0);
+
+    while let Some(index) = queue.pop_front() {
+        let next_jumps = jumps[index].unwrap_or_default() + 1;
+        for &next in &neighbors[index] {
+            if allowed[next] && jumps[next].is_none() {
+                jumps[next] = Some(next_jumps);
+                previous[next] = Some(index);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    Reach { jumps, previous }
+}
+
+/// Finds the system that every member can reach while keeping either the longest or the combined
+/// number of jumps as low as possible, breaking ties with the other. Returns None if a member's
+/// system is unknown or no system can be reached by everyone.
+pub fn find_rally_point(
+    systems: &[System],
+    connections: &[(i64, i64)],
+    member_system_names: &[String],
+    options: &RallyOptions,
+) -> Option<RallyPoint> {
+    let index_by_id: HashMap<i64, usize> = systems
+        .iter()
+        .enumerate()
+        .map(|(index, system)| (system.system_id, index))
+        .collect();
+    let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); systems.len()];
+    for (from, to) in connections {
+        if let (Some(&from), Some(&to)) = (index_by_id.get(from), index_by_id.get(to)) {
+            neighbors[from].push(to);
+        }
+    }
+    let allowed: Vec<bool> = systems
+        .iter()
+        .map(|system| {
+            !options.avoid.contains(&system.name)
+                && options
+                    .security
+                    .as_ref()
+                    .is_none_or(|bands| bands.contains(&system.security_band()))
+        })
+        .collect();
+
+    let mut members = Vec::with_capacity(member_system_names.len());
+    for name in member_system_names {
+        members.push(systems.iter().position(|s| &s.name == name)?);
+    }
+    let reaches: Vec<Reach> = members
+        .iter()
+        .map(|&member| breadth_first_search(&neighbors, &allowed, member))
+        .collect();
+
+    let (rally, max_jumps, total_jumps) = (0..systems.len())
+        .filter(|&index| allowed[index])
+        .filter_map(|index| {
+            let jumps: Option<Vec<u32>> = reaches.iter().map(|reach| reach.jumps[index]).collect();
+            let jumps = jumps?;
+            let max_jumps = jumps.iter().copied().max().unwrap_or_default();
+            let total_jumps = jumps.iter().sum();
+            Some((index, max_jumps, total_jumps))
+        })
+        .min_by_key(|&(index, max_jumps, total_jumps)| match options.objective {
+            RallyObjective::MaxJumps => (max_jumps, total_jumps, index),
+            RallyObjective::TotalJumps => (total_jumps, max_jumps, index),
+        })?;
+
+    let routes = members
+        .iter()
+        .zip(&reaches)
+        .map(|(&member, reach)| {
+            let mut route = vec![systems[rally].name.clone()];
+            let mut current = rally;
+            while let Some(previous) = reach.previous[current] {
+                route.push(systems[previous].name.clone());
+                current = previous;
+            }
+            route.reverse();
+            MemberRoute {
+                from: systems[member].name.clone(),
+                jumps: reach.jumps[rally].unwrap_or_default(),
+                route,
+            }
+        })
+        .collect();
+
+    Some(RallyPoint {
+        system: systems[rally].name.clone(),
+        max_jumps,
+        total_jumps,
+        routes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn system(system_id: i64, name: &str, security_status: f64) -> System {
+        System {
+            name: name.to_string(),
+            security_status,
+            system_id,
+            ..System::default()
+        }
+    }
+
+    /// A line of systems A - B - C - D - E, with a high-sec shortcut from A to X to E.
+    fn universe() -> (Vec<System>, Vec<(i64, i64)>) {
+        let systems = vec![
+            system(1, "A", 0.3),
+            system(2, "B", 0.3),
+            system(3, "C", 0.3),
+            system(4, "D", 0.3),
+            system(5, "E", 0.3),
+            system(6, "X", 0.9),
+        ];
+        let links = [(1, 2), (2, 3), (3, 4), (4, 5), (1, 6), (6, 5)];
+        let connections = links.iter().flat_map(|&(a, b)| [(a, b), (b, a)]).collect();
+        (systems, connections)
+    }
+
+    fn members(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_rally_point_minimizes_the_longest_trip() {
+        let (systems, connections) = universe();
+
+        let rally = find_rally_point(
+            &systems,
+            &connections,
+            &members(&["A", "E"]),
+            &RallyOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(rally.system, "X");
+        assert_eq!(rally.max_jumps, 1);
+        assert_eq!(rally.routes[0].route, vec!["A", "X"]);
+        assert_eq!(rally.routes[1].route, vec!["E", "X"]);
+    }
+
+    #[test]
+    fn test_rally_point_respects_avoid_list_and_security() {
+        let (systems, connections) = universe();
+        let options = RallyOptions {
+            objective: RallyObjective::MaxJumps,
+            avoid: vec![String::from("D")],
+            security: Some(vec![SecurityBand::Low]),
+        };
+
+        let rally =
+            find_rally_point(&systems, &connections, &members(&["A", "C"]), &options).unwrap();
+        assert_eq!(rally.system, "B");
+
+        // E can only leave through D or the high-sec system, neither of which is allowed.
+        assert!(
+            find_rally_point(&systems, &connections, &members(&["A", "E"]), &options).is_none()
+        );
+    }
+}
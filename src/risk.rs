@@ -0,0 +1,617 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::{ActivitySample, SecurityBand, System};
+use crate::dijkstra::dijkstra;
+
+/// The hour of the day (UTC) that EVE's daily downtime starts.
+pub const DOWNTIME_HOUR: u32 = 11;
+
+/// How much more a pod kill counts than a ship kill in the pod kill weighted model. Pods die in
+/// numbers at smartbomb and bubble camps, which are the places haulers most need to avoid.
+const POD_KILL_WEIGHT: f64 = 3.0;
+
+/// The activity in a system that a risk model turns into the cost of jumping into it. Counts are
+/// weighted averages of an hour over the risk window, so they needn't be whole numbers.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SystemActivity {
+    pub ship_kills: f64,
+    pub pod_kills: f64,
+    /// Kills of NPCs, a sign of players ratting rather than hunting.
+    pub npc_kills: f64,
+    pub jumps: f64,
+    pub security_status: f64,
+}
+
+/// Turns the activity in a system into the cost of jumping into it. The baseline is the risk of
+/// an average jump across the galaxy.
+pub trait RiskModel: Send + Sync {
+    fn risk(&self, activity: &SystemActivity, baseline_jump_risk: f64) -> f64;
+}
+
+/// Squares the kills in a system and spreads them over its jumps, on top of the baseline. Fewer
+/// than one jump is treated as one, so a quiet system's kills aren't inflated.
+pub fn kills_per_jump_risk(kills: f64, jumps: f64, baseline_jump_risk: f64) -> f64 {
+    let kills_squared = kills * kills;
+    kills_squared / jumps.max(1.0) + baseline_jump_risk
+}
+
+/// Squares the kills in a system and spreads them over its jumps, on top of the baseline.
+pub struct KillsPerJump;
+
+impl RiskModel for KillsPerJump {
+    fn risk(&self, activity: &SystemActivity, baseline_jump_risk: f64) -> f64 {
+        kills_per_jump_risk(activity.ship_kills, activity.jumps, baseline_jump_risk)
+    }
+}
+
+/// Kills per jump, scaled up in low and null security space where nothing stops a gate camp
+/// from forming, even when the last hour was quiet.
+pub struct SecurityWeighted;
+
+impl SecurityWeighted {
+    fn multiplier(band: SecurityBand) -> f64 {
+        match band {
+            SecurityBand::High => 1.0,
+            SecurityBand::Low => 5.0,
+            SecurityBand::Null => 10.0,
+        }
+    }
+}
+
+impl RiskModel for SecurityWeighted {
+    fn risk(&self, activity: &SystemActivity, baseline_jump_risk: f64) -> f64 {
+        KillsPerJump.risk(activity, baseline_jump_risk)
+            * Self::multiplier(SecurityBand::from_status(activity.security_status))
+    }
+}
+
+/// Kills per jump, where pod kills count several times over.
+pub struct PodKillWeighted;
+
+impl RiskModel for PodKillWeighted {
+    fn risk(&self, activity: &SystemActivity, baseline_jump_risk: f64) -> f64 {
+        let kills = activity.ship_kills + POD_KILL_WEIGHT * activity.pod_kills;
+        kills_per_jump_risk(kills, activity.jumps, baseline_jump_risk)
+    }
+}
+
+/// The built-in risk models. Every refresh computes them all, and each is stored in its own
+/// relationship property so a route can be weighted by any of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskModelKind {
+    #[default]
+    KillsPerJump,
+    SecurityWeighted,
+    PodKillWeighted,
+}
+
+impl RiskModelKind {
+    pub const ALL: [RiskModelKind; 3] = [
+        RiskModelKind::KillsPerJump,
+        RiskModelKind::SecurityWeighted,
+        RiskModelKind::PodKillWeighted,
+    ];
+
+    pub fn model(&self) -> &'static dyn RiskModel {
+        match self {
+            RiskModelKind::KillsPerJump => &KillsPerJump,
+            RiskModelKind::SecurityWeighted => &SecurityWeighted,
+            RiskModelKind::PodKillWeighted => &PodKillWeighted,
+        }
+    }
+
+    /// The relationship property the model's risk is stored in.
+    pub fn property(&self) -> &'static str {
+        match self {
+            RiskModelKind::KillsPerJump => "risk",
+            RiskModelKind::SecurityWeighted => "security_risk",
+            RiskModelKind::PodKillWeighted => "pod_kill_risk",
+        }
+    }
+
+    /// The model set with the `RISK_MODEL` environment variable, used when a request doesn't pick
+    /// one.
+    pub fn configured() -> RiskModelKind {
+        env::var("RISK_MODEL")
+            .ok()
+            .and_then(|name| {
+                RiskModelKind::deserialize(serde::de::value::StrDeserializer::<
+                    serde::de::value::Error,
+                >::new(name.trim()))
+                .ok()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// How many hours of activity a risk refresh looks at, and how quickly older activity stops
+/// counting. Activity loses half its weight every half-life.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RiskWindow {
+    pub hours: u32,
+    pub half_life_hours: f64,
+}
+
+impl Default for RiskWindow {
+    fn default() -> Self {
+        Self {
+            hours: 6,
+            half_life_hours: 2.0,
+        }
+    }
+}
+
+impl RiskWindow {
+    /// The window set with the `RISK_WINDOW_HOURS` and `RISK_HALF_LIFE_HOURS` environment
+    /// variables.
+    pub fn configured() -> RiskWindow {
+        let default = RiskWindow::default();
+        RiskWindow {
+            hours: env::var("RISK_WINDOW_HOURS")
+                .ok()
+                .and_then(|hours| hours.parse().ok())
+                .unwrap_or(default.hours)
+                .max(1),
+            half_life_hours: env::var("RISK_HALF_LIFE_HOURS")
+                .ok()
+                .and_then(|hours| hours.parse().ok())
+                .unwrap_or(default.half_life_hours),
+        }
+    }
+
+    fn weight(&self, age_hours: f64) -> f64 {
+        if self.half_life_hours > 0.0 {
+            0.5_f64.powf(age_hours / self.half_life_hours)
+        } else {
+            1.0
+        }
+    }
+
+    /// Averages the hourly activity of every system over the window ending with the hour that
+    /// starts at `current_hour`, in seconds since the epoch, weighting each hour by its age.
+    /// Security status is left for the caller to fill in.
+    pub fn decayed_activity(
+        &self,
+        samples: &[ActivitySample],
+        current_hour: i64,
+    ) -> HashMap<i64, SystemActivity> {
+        let mut sums: HashMap<i64, (SystemActivity, f64)> = HashMap::new();
+        for sample in samples {
+            let age_hours = (current_hour - sample.hour) as f64 / 3600.0;
+            if age_hours < 0.0 || age_hours >= self.hours as f64 {
+                continue;
+            }
+            let weight = self.weight(age_hours);
+            let (activity, total_weight) = sums.entry(sample.system_id).or_default();
+            activity.ship_kills += weight * sample.kills as f64;
+            activity.pod_kills += weight * sample.pod_kills as f64;
+            activity.npc_kills += weight * sample.npc_kills as f64;
+            activity.jumps += weight * sample.jumps as f64;
+            *total_weight += weight;
+        }
+        sums.into_iter()
+            .map(|(system_id, (activity, total_weight))| {
+                let activity = SystemActivity {
+                    ship_kills: activity.ship_kills / total_weight,
+                    pod_kills: activity.pod_kills / total_weight,
+                    npc_kills: activity.npc_kills / total_weight,
+                    jumps: activity.jumps / total_weight,
+                    ..activity
+                };
+                (system_id, activity)
+            })
+            .collect()
+    }
+}
+
+/// Averages the activity of every system in one hour of the day (UTC) over the days of history
+/// there are, giving the activity to expect at that time of day.
+pub fn hour_of_day_activity(
+    samples: &[ActivitySample],
+    hour_of_day: u32,
+) -> HashMap<i64, SystemActivity> {
+    let samples: Vec<&ActivitySample> = samples
+        .iter()
+        .filter(|sample| sample.hour.rem_euclid(86400) / 3600 == i64::from(hour_of_day))
+        .collect();
+    let days = samples
+        .iter()
+        .map(|sample| sample.hour)
+        .collect::<HashSet<i64>>()
+        .len()
+        .max(1) as f64;
+
+    let mut activities: HashMap<i64, SystemActivity> = HashMap::new();
+    for sample in samples {
+        let activity = activities.entry(sample.system_id).or_default();
+        activity.ship_kills += sample.kills as f64 / days;
+        activity.pod_kills += sample.pod_kills as f64 / days;
+        activity.npc_kills += sample.npc_kills as f64 / days;
+        activity.jumps += sample.jumps as f64 / days;
+    }
+    activities
+}
+
+/// Counts the kills the live kill feed saw in a system over the last hour that its latest hourly
+/// sample doesn't account for yet, by raising that sample's counts to the live ones. Live kills
+/// then weigh in the risk window exactly as much as the sampled kills of that hour. A system with
+/// no samples gets one for the hour starting at `current_hour`.
+pub fn with_live_kills(
+    samples: &[ActivitySample],
+    system_id: i64,
+    current_hour: i64,
+    live_ship_kills: u32,
+    live_pod_kills: u32,
+) -> Vec<ActivitySample> {
+    let mut samples = samples.to_vec();
+    let latest = samples
+        .iter_mut()
+        .filter(|sample| sample.system_id == system_id)
+        .max_by_key(|sample| sample.hour);
+    match latest {
+        Some(sample) => {
+            sample.kills = sample.kills.max(live_ship_kills);
+            sample.pod_kills = sample.pod_kills.max(live_pod_kills);
+        }
+        None => samples.push(ActivitySample {
+            system_id,
+            hour: current_hour,
+            kills: live_ship_kills,
+            pod_kills: live_pod_kills,
+            npc_kills: 0,
+            jumps: 0,
+        }),
+    }
+    samples
+}
+
+/// The chance of getting caught on a jump with the given risk. Risk is roughly the kills to expect
+/// on the jump, so the chance of running into at least one is 1 − e^(−risk).
+pub fn catch_probability(risk: f64) -> f64 {
+    1.0 - (-risk.max(0.0)).exp()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HopRisk {
+    pub from: String,
+    pub to: String,
+    pub risk: f64,
+    pub catch_probability: f64,
+}
+
+/// How risky a whole route is.
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteRiskSummary {
+    pub jumps: usize,
+    pub total_risk: f64,
+    /// The chance of getting caught somewhere along the route, 1 − Π(1 − p) over the jumps.
+    pub catch_probability: f64,
+    /// The riskiest jumps, riskiest first.
+    pub riskiest_hops: Vec<HopRisk>,
+}
+
+/// Sums up the risk of a route, given the risk of each of its jumps in order.
+pub fn summarize_route_risk(
+    route: &[String],
+    hop_risks: &[f64],
+    riskiest_hop_count: usize,
+) -> RouteRiskSummary {
+    let mut hops: Vec<HopRisk> = route
+        .windows(2)
+        .zip(hop_risks)
+        .map(|(pair, &risk)| HopRisk {
+            from: pair[0].clone(),
+            to: pair[1].clone(),
+            risk,
+            catch_probability: catch_probability(risk),
+        })
+        .collect();
+    let total_risk = hops.iter().map(|hop| hop.risk).sum();
+    let safe_probability: f64 = hops.iter().map(|hop| 1.0 - hop.catch_probability).product();
+
+    hops.sort_by(|a, b| b.risk.total_cmp(&a.risk));
+    hops.truncate(riskiest_hop_count);
+    RouteRiskSummary {
+        jumps: route.len().saturating_sub(1),
+        total_risk,
+        catch_probability: 1.0 - safe_probability,
+        riskiest_hops: hops,
+    }
+}
+
+/// The risk of an average jump across the galaxy, given the activity of every system.
+pub fn baseline_jump_risk(activities: &HashMap<i64, SystemActivity>) -> f64 {
+    let galaxy_kills: f64 = activities.values().map(|a| a.ship_kills).sum();
+    let galaxy_jumps: f64 = activities.values().map(|a| a.jumps).sum();
+    if galaxy_jumps > 0.0 {
+        galaxy_kills / galaxy_jumps
+    } else {
+        0.01 // galaxy jumps should never be zero, but just in case
+    }
+}
+
+/// The risk of jumping into every system under a risk model, given the activity in them.
+pub fn system_risks(
+    systems: &[System],
+    activities: &HashMap<i64, SystemActivity>,
+    model: RiskModelKind,
+) -> HashMap<i64, f64> {
+    let baseline = baseline_jump_risk(activities);
+    systems
+        .iter()
+        .map(|system| {
+            let activity = SystemActivity {
+                security_status: system.security_status,
+                ..activities
+                    .get(&system.system_id)
+                    .copied()
+                    .unwrap_or_default()
+            };
+            (system.system_id, model.model().risk(&activity, baseline))
+        })
+        .collect()
+}
+
+/// Finds the route with the least risk, where jumping into a system costs its risk. Systems
+/// without a risk cost nothing.
+pub fn find_lowest_risk_route(
+    systems: &[System],
+    connections: &[(i64, i64)],
+    risks: &HashMap<i64, f64>,
+    from_system_name: &str,
+    to_system_name: &str,
+) -> Option<Vec<String>> {
+    let index_by_id: HashMap<i64, usize> = systems
+        .iter()
+        .enumerate()
+        .map(|(index, system)| (system.system_id, index))
+        .collect();
+    let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); systems.len()];
+    for (from, to) in connections {
+        if let (Some(&from), Some(&to)) = (index_by_id.get(from), index_by_id.get(to)) {
+            neighbors[from].push(to);
+        }
+    }
+    let source = systems.iter().position(|s| s.name == from_system_name)?;
+    let target = systems.iter().position(|s| s.name == to_system_name)?;
+
+    let path = dijkstra(
+        systems.len(),
+        source,
+        0.0,
+        |system| system == target,
+        |&risk| risk,
+        |system, &risk| {
+            neighbors[system]
+                .iter()
+                .map(|&next| {
+                    let next_risk =
+                        risk + risks.get(&systems[next].system_id).copied().unwrap_or(0.0);
+                    (next, (), next_risk)
+                })
+                .collect()
+        },
+    )?;
+
+    let mut route = vec![systems[source].name.clone()];
+    route.extend(
+        path.steps
+            .iter()
+            .map(|&(_, _, system)| systems[system].name.clone()),
+    );
+    Some(route)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn activity(
+        ship_kills: u32,
+        pod_kills: u32,
+        jumps: u32,
+        security_status: f64,
+    ) -> SystemActivity {
+        SystemActivity {
+            ship_kills: ship_kills as f64,
+            pod_kills: pod_kills as f64,
+            npc_kills: 0.0,
+            jumps: jumps as f64,
+            security_status,
+        }
+    }
+
+    fn sample(hours_ago: i64, kills: u32, jumps: u32) -> ActivitySample {
+        ActivitySample {
+            system_id: 30000142,
+            hour: 100 * 3600 - hours_ago * 3600,
+            kills,
+            pod_kills: 0,
+            npc_kills: 0,
+            jumps,
+        }
+    }
+
+    #[test]
+    fn test_security_weighted_penalizes_quiet_null_sec() {
+        let quiet_high_sec = activity(0, 0, 100, 0.9);
+        let quiet_null_sec = activity(0, 0, 100, 0.0);
+
+        assert_eq!(KillsPerJump.risk(&quiet_null_sec, 0.1), 0.1);
+        assert_eq!(SecurityWeighted.risk(&quiet_high_sec, 0.1), 0.1);
+        assert_eq!(SecurityWeighted.risk(&quiet_null_sec, 0.1), 1.0);
+    }
+
+    #[test]
+    fn test_pod_kill_weighted_counts_pods_more() {
+        let ships_only = activity(4, 0, 100, 0.0);
+        let pods = activity(1, 1, 100, 0.0);
+
+        // (1 + 3 * 1)^2 / 100 + 0.1 = 0.26, the same as four ship kills.
+        assert!((PodKillWeighted.risk(&pods, 0.1) - 0.26).abs() < 1e-9);
+        assert_eq!(
+            PodKillWeighted.risk(&pods, 0.1),
+            PodKillWeighted.risk(&ships_only, 0.1)
+        );
+        assert!(KillsPerJump.risk(&pods, 0.1) < PodKillWeighted.risk(&pods, 0.1));
+    }
+
+    #[test]
+    fn test_decayed_activity_halves_every_half_life() {
+        let window = RiskWindow {
+            hours: 6,
+            half_life_hours: 2.0,
+        };
+        let samples = [
+            sample(0, 2, 100),
+            sample(2, 20, 100),
+            sample(4, 4, 100),
+            sample(6, 1000, 100),
+        ];
+
+        let activity = window.decayed_activity(&samples, 100 * 3600)[&30000142];
+
+        // The sample six hours ago falls outside the window, and the rest are weighted 1, 1/2
+        // and 1/4.
+        assert_eq!(activity.ship_kills, (2.0 + 10.0 + 1.0) / 1.75);
+        assert_eq!(activity.jumps, 100.0);
+    }
+
+    #[test]
+    fn test_hour_of_day_activity_averages_over_days() {
+        let at = |hour: i64, kills: u32| ActivitySample {
+            system_id: 30000142,
+            hour: hour * 3600,
+            kills,
+            pod_kills: 0,
+            npc_kills: 0,
+            jumps: 10,
+        };
+        let samples = [at(18, 4), at(18 + 24, 8), at(19, 100), at(19 + 24, 100)];
+
+        let activity = hour_of_day_activity(&samples, 18)[&30000142];
+
+        assert_eq!(activity.ship_kills, 6.0);
+        assert_eq!(activity.jumps, 10.0);
+    }
+
+    #[test]
+    fn test_lowest_risk_route_goes_around_risky_systems() {
+        let system = |system_id: i64, name: &str| System {
+            name: name.to_string(),
+            security_status: 0.5,
+            system_id,
+            ..System::default()
+        };
+        // A - B - D is short but B is camped, A - C1 - C2 - D is long but quiet.
+        let systems = vec![
+            system(1, "A"),
+            system(2, "B"),
+            system(3, "C1"),
+            system(4, "C2"),
+            system(5, "D"),
+        ];
+        let links = [(1, 2), (2, 5), (1, 3), (3, 4), (4, 5)];
+        let connections: Vec<(i64, i64)> =
+            links.iter().flat_map(|&(a, b)| [(a, b), (b, a)]).collect();
+        let risks = HashMap::from([(1, 0.1), (2, 5.0), (3, 0.1), (4, 0.1), (5, 0.1)]);
+
+        let route = find_lowest_risk_route(&systems, &connections, &risks, "A", "D").unwrap();
+
+        assert_eq!(route, vec!["A", "C1", "C2", "D"]);
+        assert!(find_lowest_risk_route(&systems, &[], &risks, "A", "D").is_none());
+    }
+
+    #[test]
+    fn test_live_kills_only_add_what_the_hourly_sample_missed() {
+        let samples = [sample(0, 3, 100), sample(1, 5, 100)];
+
+        let live = with_live_kills(&samples, 30000142, 100 * 3600, 8, 2);
+        assert_eq!((live[0].kills, live[0].pod_kills), (8, 2));
+        assert_eq!(live[1], samples[1]);
+
+        let quiet = with_live_kills(&samples, 30000142, 100 * 3600, 1, 0);
+        assert_eq!(quiet, samples);
+    }
+
+    #[test]
+    fn test_live_kill_counts_the_same_as_a_sampled_kill() {
+        let window = RiskWindow {
+            hours: 24,
+            half_life_hours: 6.0,
+        };
+        let samples: Vec<ActivitySample> =
+            (0..24).map(|hours_ago| sample(hours_ago, 2, 50)).collect();
+        let mut sampled = samples.clone();
+        sampled[0].kills += 1;
+
+        let risk = |samples: &[ActivitySample]| {
+            let activity = window.decayed_activity(samples, 100 * 3600)[&30000142];
+            KillsPerJump.risk(&activity, 0.1)
+        };
+        let live = with_live_kills(&samples, 30000142, 100 * 3600, 3, 0);
+
+        assert_eq!(risk(&live), risk(&sampled));
+        assert!(risk(&live) > risk(&samples));
+    }
+
+    #[test]
+    fn test_route_risk_summary() {
+        let route: Vec<String> = ["Jita", "Perimeter", "Niarja", "Madirmilire"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let hop_risks = [0.1, 2.0, 0.5];
+
+        let summary = summarize_route_risk(&route, &hop_risks, 2);
+
+        assert_eq!(summary.jumps, 3);
+        assert!((summary.total_risk - 2.6).abs() < 1e-9);
+        // Surviving every jump is e^(−0.1) · e^(−2.0) · e^(−0.5) = e^(−2.6).
+        assert!((summary.catch_probability - (1.0 - (-2.6_f64).exp())).abs() < 1e-9);
+        assert_eq!(summary.riskiest_hops.len(), 2);
+        assert_eq!(summary.riskiest_hops[0].to, "Niarja");
+        assert_eq!(summary.riskiest_hops[1].to, "Madirmilire");
+        assert_eq!(catch_probability(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_calculate_total_risk_no_activity() {
+        // With no system activity, risk should just be the baseline.
+        let risk = kills_per_jump_risk(0.0, 0.0, 0.1);
+        assert_eq!(risk, 0.1);
+    }
+
+    #[test]
+    fn test_calculate_total_risk_with_kills_no_jumps() {
+        // With no jumps, risk is kills_squared + baseline.
+        let risk = kills_per_jump_risk(5.0, 0.0, 0.1);
+        assert_eq!(risk, 25.1);
+    }
+
+    #[test]
+    fn test_calculate_total_risk_with_jumps_no_kills() {
+        // With no kills, risk should just be the baseline.
+        let risk = kills_per_jump_risk(0.0, 100.0, 0.1);
+        assert_eq!(risk, 0.1);
+    }
+    #[test]
+    fn test_calculate_total_risk_normal_activity() {
+        // (10^2 / 200) + 0.1 = 100 / 200 + 0.1 = 0.5 + 0.1 = 0.6
+        let risk = kills_per_jump_risk(10.0, 200.0, 0.1);
+        assert!((risk - 0.6).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_kills_per_jump_risk_treats_less_than_one_jump_as_one() {
+        // Averaged activity can have a fraction of a jump, which would otherwise inflate the kills.
+        assert_eq!(
+            kills_per_jump_risk(2.0, 0.25, 0.1),
+            kills_per_jump_risk(2.0, 1.0, 0.1)
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+
+use crate::api_error::ErrorBody;
+use crate::database::{RouteMode, RouteOptions};
+
+/// The most routes a single batch may ask for.
+pub const MAX_BATCH_ROUTES: usize = 200;
+
+/// One route in a batch. Systems are given by ID or by name ignoring case.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteBatchItem {
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub mode: RouteMode,
+    #[serde(default, flatten)]
+    pub options: RouteOptions,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RouteBatchRequest {
+    pub routes: Vec<RouteBatchItem>,
+}
+
+/// The outcome of one route in a batch, in the same place as it was asked for. Either `route` or
+/// `error` is set.
+#[derive(Debug, Serialize)]
+pub struct RouteBatchResult {
+    pub from: String,
+    pub to: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub route: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jumps: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorBody>,
+}
+
+/// Routes that start from the same system with the same mode and options, so one search from the
+/// source answers all of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceGroup {
+    pub from: String,
+    pub mode: RouteMode,
+    pub options: RouteOptions,
+    pub destinations: Vec<String>,
+    /// Where each route in the group was in the batch, and the system it goes to.
+    pub routes: Vec<(usize, String)>,
+}
+
+/// Groups resolved routes, given as their position in the batch, their resolved ends and the
+/// item they came from, by the search that answers them. Groups keep the order their first route
+/// was asked in.
+pub fn group_by_source<'a>(
+    routes: impl IntoIterator<Item = (usize, String, String, &'a RouteBatchItem)>,
+) -> Vec<SourceGroup> {
+    let mut groups: Vec<SourceGroup> = Vec::new();
+    for (position, from, to, item) in routes {
+        let group = groups.iter_mut().find(|group| {
+            group.from == from && group.mode == item.mode && group.options == item.options
+        });
+        match group {
+            Some(group) => {
+                if !group.destinations.contains(&to) {
+                    group.destinations.push(to.clone());
+                }
+                group.routes.push((position, to));
+            }
+            None => groups.push(SourceGroup {
+                from,
+                mode: item.mode,
+                options: item.options.clone(),
+                destinations: vec![to.clone()],
+                routes: vec![(position, to)],
+            }),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(from: &str, to: &str, mode: RouteMode) -> RouteBatchItem {
+        RouteBatchItem {
+            from: from.to_string(),
+            to: to.to_string(),
+            mode,
+            options: RouteOptions::default(),
+        }
+    }
+
+    #[test]
+    fn test_routes_from_the_same_source_share_a_search() {
+        let mut with_bridges = item("Jita", "Amarr", RouteMode::Shortest);
+        with_bridges.options.bridges = true;
+        let items = [
+            item("Jita", "Amarr", RouteMode::Shortest),
+            item("Jita", "Dodixie", RouteMode::Shortest),
+            item("Jita", "Amarr", RouteMode::Safest),
+            with_bridges,
+            item("Rens", "Jita", RouteMode::Shortest),
+            item("Jita", "Amarr", RouteMode::Shortest),
+        ];
+
+        let groups = group_by_source(
+            items
+                .iter()
+                .enumerate()
+                .map(|(position, item)| (position, item.from.clone(), item.to.clone(), item)),
+        );
+
+        assert_eq!(groups.len(), 4);
+        assert_eq!(groups[0].destinations, vec!["Amarr", "Dodixie"]);
+        let positions: Vec<usize> = groups[0]
+            .routes
+            .iter()
+            .map(|(position, _)| *position)
+            .collect();
+        assert_eq!(positions, vec![0, 1, 5]);
+        assert_eq!(groups[1].mode, RouteMode::Safest);
+        assert!(groups[2].options.bridges);
+        assert_eq!(groups[3].from, "Rens");
+    }
+
+    #[test]
+    fn test_batch_items_default_to_shortest_routes() {
+        let request: RouteBatchRequest = serde_json::from_value(serde_json::json!({
+            "routes": [
+                { "from": "Jita", "to": "Amarr" },
+                { "from": "Jita", "to": "Rens", "mode": "safest", "bridges": true, "avoid_gates": "region" },
+            ],
+        }))
+        .unwrap();
+
+        assert_eq!(request.routes[0].mode, RouteMode::Shortest);
+        assert!(request.routes[0].options.wormholes);
+        assert_eq!(request.routes[1].mode, RouteMode::Safest);
+        assert!(request.routes[1].options.bridges);
+        assert_eq!(request.routes[1].options.avoid_gates.len(), 1);
+    }
+}
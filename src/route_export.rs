@@ -0,0 +1,130 @@
+use serde::Deserialize;
+
+/// The type ID EVE's `showinfo` links use for solar systems.
+const SOLAR_SYSTEM_TYPE_ID: i64 = 5;
+const DOTLAN_ROUTE_URL: &str = "https://evemaps.dotlan.net/route/";
+
+/// How a route is rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteFormat {
+    /// The route as JSON, with any details asked for.
+    #[default]
+    Json,
+    /// Links to every system, to paste into EVE's chat.
+    ChatLink,
+    /// The system IDs to set as autopilot waypoints through ESI, in order.
+    Waypoints,
+    /// The route on DOTLAN's map.
+    Dotlan,
+}
+
+impl RouteFormat {
+    pub const ALL: [RouteFormat; 4] = [
+        RouteFormat::Json,
+        RouteFormat::ChatLink,
+        RouteFormat::Waypoints,
+        RouteFormat::Dotlan,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteFormat::Json => "json",
+            RouteFormat::ChatLink => "chat_link",
+            RouteFormat::Waypoints => "waypoints",
+            RouteFormat::Dotlan => "dotlan",
+        }
+    }
+
+    /// The format to export the route in, or None when it's answered with JSON as usual.
+    pub fn export_format(&self) -> Option<ExportFormat> {
+        match self {
+            RouteFormat::Json => None,
+            RouteFormat::ChatLink => Some(ExportFormat::ChatLink),
+            RouteFormat::Waypoints => Some(ExportFormat::Waypoints),
+            RouteFormat::Dotlan => Some(ExportFormat::Dotlan),
+        }
+    }
+}
+
+/// Every format a route can be exported in instead of JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    ChatLink,
+    Waypoints,
+    Dotlan,
+}
+
+/// Renders the route, given as system IDs and names, as `showinfo` links separated by arrows.
+/// EVE turns each into a clickable link to the system when pasted into chat.
+pub fn chat_link(route: &[(i64, String)]) -> String {
+    route
+        .iter()
+        .map(|(system_id, name)| {
+            format!("<url=showinfo:{SOLAR_SYSTEM_TYPE_ID}//{system_id}>{name}</url>")
+        })
+        .collect::<Vec<_>>()
+        .join(" > ")
+}
+
+/// The systems to add as waypoints, one `/ui/autopilot/waypoint/` call each. The first system is
+/// left out, since the pilot is already there, and every other system is kept so the autopilot
+/// follows this route instead of picking its own.
+pub fn waypoints(route: &[(i64, String)]) -> Vec<i64> {
+    route
+        .iter()
+        .skip(1)
+        .map(|(system_id, _)| *system_id)
+        .collect()
+}
+
+/// A DOTLAN route URL through every system of the route. DOTLAN separates systems with colons
+/// and writes spaces in names as underscores.
+pub fn dotlan_url(route: &[(i64, String)]) -> String {
+    let systems: Vec<String> = route
+        .iter()
+        .map(|(_, name)| name.replace(' ', "_"))
+        .collect();
+    format!("{DOTLAN_ROUTE_URL}{}", systems.join(":"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route() -> Vec<(i64, String)> {
+        vec![
+            (30002187, String::from("Amarr")),
+            (30002188, String::from("Amarr Prime")),
+            (30002510, String::from("Rens")),
+        ]
+    }
+
+    #[test]
+    fn test_route_renders_as_chat_link_and_dotlan_url() {
+        assert_eq!(
+            chat_link(&route()),
+            "<url=showinfo:5//30002187>Amarr</url> > <url=showinfo:5//30002188>Amarr Prime</url> \
+             > <url=showinfo:5//30002510>Rens</url>"
+        );
+        assert_eq!(
+            dotlan_url(&route()),
+            "https://evemaps.dotlan.net/route/Amarr:Amarr_Prime:Rens"
+        );
+    }
+
+    #[test]
+    fn test_every_format_but_json_is_exported() {
+        assert_eq!(RouteFormat::Json.export_format(), None);
+        assert_eq!(
+            RouteFormat::Waypoints.export_format(),
+            Some(ExportFormat::Waypoints)
+        );
+    }
+
+    #[test]
+    fn test_waypoints_leave_out_the_starting_system() {
+        assert_eq!(waypoints(&route()), vec![30002188, 30002510]);
+        assert!(waypoints(&route()[..1]).is_empty());
+    }
+}
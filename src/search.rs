@@ -0,0 +1,223 @@
+use serde::Serialize;
+
+/// How a system's name matched a search, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Exact,
+    Prefix,
+    Fuzzy,
+}
+
+impl MatchKind {
+    pub const ALL: [MatchKind; 3] = [MatchKind::Exact, MatchKind::Prefix, MatchKind::Fuzzy];
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SystemMatch {
+    pub system_id: i64,
+    pub name: String,
+    #[serde(rename = "match")]
+    pub kind: MatchKind,
+    /// How many typos apart the search and the name are, for fuzzy matches.
+    pub distance: usize,
+}
+
+/// How many typos a search of this many characters may contain and still match.
+fn allowed_typos(length: usize) -> usize {
+    match length {
+        0..=2 => 0,
+        3..=5 => 1,
+        6..=9 => 2,
+        _ => 3,
+    }
+}
+
+/// The edit distance between two strings, counting swapped neighbouring characters as one typo.
+fn typo_distance(a: &[char], b: &[char]) -> usize {
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + substitution);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+/// How a name matches a search, if it does. A fuzzy match compares the search against the whole
+/// name and against the start of it, so a typo in a partly typed name still matches.
+pub fn match_name(search: &str, name: &str) -> Option<(MatchKind, usize)> {
+    let search: Vec<char> = search.trim().to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    if search.is_empty() {
+        return None;
+    }
+    if search == name {
+        return Some((MatchKind::Exact, 0));
+    }
+    if name.starts_with(&search) {
+        return Some((MatchKind::Prefix, 0));
+    }
+
+    let start = &name[..search.len().min(name.len())];
+    let distance = typo_distance(&search, &name).min(typo_distance(&search, start));
+    (distance <= allowed_typos(search.len())).then_some((MatchKind::Fuzzy, distance))
+}
+
+/// Finds the systems whose names best match a search, ignoring case. Exact matches come first,
+/// then names starting with the search, shortest first, then names with the fewest typos.
+pub fn search_systems(systems: &[(i64, String)], search: &str, limit: usize) -> Vec<SystemMatch> {
+    let mut matches: Vec<SystemMatch> = systems
+        .iter()
+        .filter_map(|(system_id, name)| {
+            match_name(search, name).map(|(kind, distance)| SystemMatch {
+                system_id: *system_id,
+                name: name.clone(),
+                kind,
+                distance,
+            })
+        })
+        .collect();
+    matches.sort_by(|a, b| {
+        a.kind
+            .cmp(&b.kind)
+            .then(a.distance.cmp(&b.distance))
+            .then(a.name.len().cmp(&b.name.len()))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    matches.truncate(limit);
+    matches
+}
+
+/// How many suggestions come with a system that couldn't be resolved.
+const SUGGESTION_COUNT: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnresolvedReason {
+    /// No system has the name or ID.
+    Unknown,
+    /// More than one system has the name when ignoring case.
+    Ambiguous,
+}
+
+/// A system name or ID that doesn't pick out exactly one system, with the closest systems to
+/// suggest instead.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnresolvedSystem {
+    pub input: String,
+    pub reason: UnresolvedReason,
+    pub suggestions: Vec<String>,
+}
+
+/// Resolves a system ID, or a system name ignoring case, to the system's name.
+pub fn resolve_system(systems: &[(i64, String)], input: &str) -> Result<String, UnresolvedSystem> {
+    let input = input.trim();
+    let unresolved = |reason, suggestions| UnresolvedSystem {
+        input: input.to_string(),
+        reason,
+        suggestions,
+    };
+
+    if let Ok(system_id) = input.parse::<i64>() {
+        return systems
+            .iter()
+            .find(|(id, _)| *id == system_id)
+            .map(|(_, name)| name.clone())
+            .ok_or_else(|| unresolved(UnresolvedReason::Unknown, Vec::new()));
+    }
+
+    let mut exact = systems
+        .iter()
+        .filter(|(_, name)| name.eq_ignore_ascii_case(input))
+        .map(|(_, name)| name.clone());
+    match (exact.next(), exact.next()) {
+        (Some(name), None) => Ok(name),
+        (Some(first), Some(second)) => {
+            let mut names = vec![first, second];
+            names.extend(exact);
+            Err(unresolved(UnresolvedReason::Ambiguous, names))
+        }
+        (None, _) => {
+            let suggestions = search_systems(systems, input, SUGGESTION_COUNT)
+                .into_iter()
+                .map(|m| m.name)
+                .collect();
+            Err(unresolved(UnresolvedReason::Unknown, suggestions))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn systems() -> Vec<(i64, String)> {
+        [
+            (30000142, "Jita"),
+            (30002187, "Amarr"),
+            (30002188, "Amarr Prime"),
+            (30002659, "Dodixie"),
+            (30002510, "Rens"),
+        ]
+        .into_iter()
+        .map(|(system_id, name)| (system_id, name.to_string()))
+        .collect()
+    }
+
+    fn names(matches: &[SystemMatch]) -> Vec<&str> {
+        matches.iter().map(|m| m.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_search_ignores_case_and_ranks_exact_before_prefix() {
+        assert_eq!(names(&search_systems(&systems(), "jita", 10)), vec!["Jita"]);
+        assert_eq!(
+            names(&search_systems(&systems(), "amarr", 10)),
+            vec!["Amarr", "Amarr Prime"]
+        );
+        assert_eq!(
+            names(&search_systems(&systems(), "Amar", 10)),
+            vec!["Amarr", "Amarr Prime"]
+        );
+    }
+
+    #[test]
+    fn test_search_tolerates_typos() {
+        let matches = search_systems(&systems(), "dodxie", 10);
+        assert_eq!(names(&matches), vec!["Dodixie"]);
+        assert_eq!(matches[0].kind, MatchKind::Fuzzy);
+
+        assert_eq!(names(&search_systems(&systems(), "jtia", 10)), vec!["Jita"]);
+        assert!(search_systems(&systems(), "xyz", 10).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_system_by_name_or_id() {
+        assert_eq!(resolve_system(&systems(), "jita").unwrap(), "Jita");
+        assert_eq!(resolve_system(&systems(), "30002187").unwrap(), "Amarr");
+
+        let unknown = resolve_system(&systems(), "Amar").unwrap_err();
+        assert_eq!(unknown.reason, UnresolvedReason::Unknown);
+        assert_eq!(unknown.suggestions, vec!["Amarr", "Amarr Prime"]);
+
+        let mut duplicated = systems();
+        duplicated.push((1, String::from("JITA")));
+        let ambiguous = resolve_system(&duplicated, "Jita").unwrap_err();
+        assert_eq!(ambiguous.reason, UnresolvedReason::Ambiguous);
+        assert_eq!(ambiguous.suggestions, vec!["Jita", "JITA"]);
+    }
+}
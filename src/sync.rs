@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+use std::env;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 // todo: remove direct dependency on neo4rs
 use neo4rs::Graph;
@@ -6,9 +9,25 @@ use reqwest::Client;
 use thiserror::Error;
 use tokio::sync::Semaphore;
 use tokio::task::{JoinError, JoinSet};
-use tracing::{error, info, instrument};
+use tracing::{error, info, instrument, warn};
 
-use crate::{database, esi, eve_scout};
+use crate::database::GateType;
+use crate::events::{GraphEvent, RiskChange, SyncJob, WormholeConnection};
+use crate::risk::RiskModelKind;
+use crate::risk::{RiskWindow, SystemActivity, baseline_jump_risk, with_live_kills};
+use crate::{alerts, database, esi, eve_scout, events, zkillboard};
+
+/// How long to wait before listening to the kill feed again after it failed.
+const KILL_FEED_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// How often the kill feed rebuilds the `jump-risk` projection while kills are coming in.
+const KILL_FEED_PROJECTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many of the systems whose risk changed the most a risk refresh reports.
+const MOST_CHANGED_SYSTEMS: usize = 10;
+
+/// Two weeks of hourly activity.
+const DEFAULT_ACTIVITY_RETENTION_HOURS: i64 = 336;
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -16,6 +35,8 @@ pub enum Error {
     EsiSource(#[from] esi::Error),
     #[error("failed to retrieve the data from eve scout")]
     EveScoutSource(#[from] eve_scout::Error),
+    #[error("failed to retrieve the data from zkillboard")]
+    ZKillboardSource(#[from] zkillboard::Error),
     #[error("failed to process the data")]
     Process(#[from] JoinError),
     #[error("failed to persist data to the target")]
@@ -42,7 +63,13 @@ impl From<esi::SystemResponse> for database::System {
             stargates: s.stargates.unwrap_or_default(),
             system_id: s.system_id,
             kills: 0,
+            pod_kills: 0,
+            npc_kills: 0,
             jumps: 0,
+            region_id: None,
+            region_name: None,
+            constellation_name: None,
+            risk: None,
         }
     }
 }
@@ -68,10 +95,11 @@ pub async fn refresh_eve_scout_system_relations(
     graph: Arc<Graph>,
 ) -> Result<(), Error> {
     info!("Refreshing EVE Scout public connections");
-    database::drop_system_connections(&graph, "Thera")
+    let before = wormhole_connections(graph.clone()).await?;
+    database::drop_system_wormholes(&graph, "Thera")
         .await
         .map_err(Error::Target)?;
-    database::drop_system_connections(&graph, "Turnur")
+    database::drop_system_wormholes(&graph, "Turnur")
         .await
         .map_err(Error::Target)?;
 
@@ -86,13 +114,33 @@ pub async fn refresh_eve_scout_system_relations(
                 graph.clone(),
                 wormhole.in_system_id,
                 wormhole.out_system_id,
+                Some(wormhole.max_ship_size.clone()).filter(|size| !size.is_empty()),
             ));
         });
 
     error_if_any_member_has_error(&mut set).await.unwrap()?;
+
+    let after = wormhole_connections(graph).await?;
+    let (added, removed) = events::wormhole_changes(&before, &after);
+    if !added.is_empty() || !removed.is_empty() {
+        events::publish(GraphEvent::WormholesChanged { added, removed });
+    }
+    events::publish(GraphEvent::SyncFinished {
+        job: SyncJob::Wormholes,
+    });
     Ok(())
 }
 
+async fn wormhole_connections(graph: Arc<Graph>) -> Result<Vec<WormholeConnection>, Error> {
+    let connections = database::get_wormhole_connections(graph)
+        .await
+        .map_err(Error::Target)?;
+    Ok(connections
+        .into_iter()
+        .map(|(from, to)| WormholeConnection { from, to })
+        .collect())
+}
+
 async fn pull_stargates(
     client: Client,
     graph: Arc<Graph>,
@@ -160,15 +208,52 @@ pub async fn synchronize_esi_systems(client: Client, graph: Arc<Graph>) -> Resul
         .await
         .map_err(Error::Target)?;
 
+    synchronize_esi_regions(client, graph.clone()).await?;
+
     let final_count = database::get_saved_system_count(&graph).await?;
     info!(
         "System synchronization complete. Total systems: {}",
         final_count
     );
+    events::publish(GraphEvent::SyncFinished {
+        job: SyncJob::Systems,
+    });
 
     Ok(())
 }
 
+/// Tags every system with the region its constellation belongs to and its constellation's name.
+async fn synchronize_esi_regions(client: Client, graph: Arc<Graph>) -> Result<(), Error> {
+    info!("Synchronizing system regions with ESI");
+    let region_ids = esi::get_region_ids(&client).await?;
+    let mut set = JoinSet::new();
+
+    for region_id in region_ids {
+        let client = client.clone();
+        let graph = graph.clone();
+        set.spawn(async move {
+            let region = esi::get_region_details(&client, region_id).await?;
+            let constellations = esi::get_names(&client, &region.constellations).await?;
+            database::set_system_regions(
+                graph.clone(),
+                region.region_id,
+                region.name,
+                region.constellations,
+            )
+            .await?;
+            let (constellation_ids, constellation_names) = constellations
+                .into_iter()
+                .map(|constellation| (constellation.id, constellation.name))
+                .unzip();
+            database::set_constellation_names(graph, constellation_ids, constellation_names)
+                .await?;
+            Ok(())
+        });
+    }
+
+    error_if_any_member_has_error(&mut set).await.unwrap()
+}
+
 pub async fn synchronize_esi_stargates(client: Client, graph: Arc<Graph>) -> Result<(), Error> {
     info!("Synchronizing stargates with ESI");
 
@@ -212,11 +297,52 @@ pub async fn synchronize_esi_stargates(client: Client, graph: Arc<Graph>) -> Res
         .await
         .map_err(Error::Target)?;
 
+    info!("Linking jumps to the stargates they go through.");
+    database::link_stargate_jumps(graph.clone())
+        .await
+        .map_err(Error::Target)?;
+
+    info!("Setting the gate type of jumps from their stargate types.");
+    set_gate_types(&client, graph.clone()).await?;
+
     let final_count = database::get_saved_stargate_count(&graph).await?;
     info!(
         "Stargate synchronization complete. Total stargates: {}",
         final_count
     );
+    events::publish(GraphEvent::SyncFinished {
+        job: SyncJob::Stargates,
+    });
+    Ok(())
+}
+
+/// Sets the gate type of every stargate jump from the name of its stargate's type, which ends in
+/// "System", "Constellation" or "Region". Jumps through stargates of any other type keep no gate
+/// type and are treated as system gates.
+async fn set_gate_types(client: &Client, graph: Arc<Graph>) -> Result<(), Error> {
+    let type_ids = database::get_stargate_type_ids(graph.clone())
+        .await
+        .map_err(Error::Target)?;
+    let mut type_ids_by_gate_type: HashMap<GateType, Vec<i64>> = HashMap::new();
+    for chunk in type_ids.chunks(1000) {
+        for stargate_type in esi::get_names(client, chunk).await? {
+            match GateType::from_type_name(&stargate_type.name) {
+                Some(gate_type) => type_ids_by_gate_type
+                    .entry(gate_type)
+                    .or_default()
+                    .push(stargate_type.id),
+                None => warn!(
+                    "Stargate type {} ({}) has no known gate type",
+                    stargate_type.id, stargate_type.name
+                ),
+            }
+        }
+    }
+    for (gate_type, type_ids) in type_ids_by_gate_type {
+        database::set_stargate_gate_types(graph.clone(), gate_type, type_ids)
+            .await
+            .map_err(Error::Target)?;
+    }
     Ok(())
 }
 
@@ -251,9 +377,11 @@ async fn error_if_any_member_has_error<T: 'static>(
     Some(Ok(()))
 }
 
-pub async fn pull_system_kills(client: Client, graph: Arc<Graph>) -> Result<i32, Error> {
+pub async fn pull_system_kills(
+    client: Client,
+    graph: Arc<Graph>,
+) -> Result<Vec<esi::SystemKills>, Error> {
     let system_kills = esi::get_system_kills(&client).await?;
-    let galaxy_kills: i32 = system_kills.iter().map(|s| s.ship_kills).sum();
 
     let mut set = JoinSet::new();
 
@@ -262,19 +390,23 @@ pub async fn pull_system_kills(client: Client, graph: Arc<Graph>) -> Result<i32,
             graph.clone(),
             system_kill.system_id,
             system_kill.ship_kills,
+            system_kill.pod_kills,
+            system_kill.npc_kills,
         ));
     });
 
     error_if_any_member_has_error(&mut set)
         .await
         .unwrap()
-        .map(|_| galaxy_kills)
+        .map(|_| system_kills)
         .map_err(Error::Target)
 }
 
-pub async fn pull_last_hour_of_jumps(client: Client, graph: Arc<Graph>) -> Result<i32, Error> {
+pub async fn pull_last_hour_of_jumps(
+    client: Client,
+    graph: Arc<Graph>,
+) -> Result<Vec<esi::SystemJumps>, Error> {
     let system_jumps = esi::get_system_jumps(&client).await?;
-    let galaxy_jumps: i32 = system_jumps.iter().map(|s| s.ship_jumps).sum();
 
     let mut set = JoinSet::new();
 
@@ -289,27 +421,115 @@ pub async fn pull_last_hour_of_jumps(client: Client, graph: Arc<Graph>) -> Resul
     error_if_any_member_has_error(&mut set)
         .await
         .unwrap()
-        .map(|_| galaxy_jumps)
+        .map(|_| system_jumps)
         .map_err(Error::Target)
 }
 
+/// The current time, in seconds since the epoch.
+pub fn now_seconds() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// The start of the last full hour, in seconds since the epoch. ESI's hourly kill and jump counts
+/// describe this hour.
+fn last_full_hour() -> i64 {
+    let now = now_seconds();
+    now - now % 3600 - 3600
+}
+
+/// How many hours of activity history to keep, set with the `ACTIVITY_RETENTION_HOURS`
+/// environment variable.
+fn activity_retention_hours() -> i64 {
+    env::var("ACTIVITY_RETENTION_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse().ok())
+        .unwrap_or(DEFAULT_ACTIVITY_RETENTION_HOURS)
+}
+
+/// Combines the kills and jumps ESI reported for the last hour into one sample per system.
+fn activity_samples(
+    hour: i64,
+    system_kills: &[esi::SystemKills],
+    system_jumps: &[esi::SystemJumps],
+) -> Vec<database::ActivitySample> {
+    let mut samples: HashMap<i64, database::ActivitySample> = HashMap::new();
+    let empty_sample = |system_id| database::ActivitySample {
+        system_id,
+        hour,
+        kills: 0,
+        pod_kills: 0,
+        npc_kills: 0,
+        jumps: 0,
+    };
+
+    for system_kill in system_kills {
+        let sample = samples
+            .entry(system_kill.system_id)
+            .or_insert_with(|| empty_sample(system_kill.system_id));
+        sample.kills = system_kill.ship_kills.max(0) as u32;
+        sample.pod_kills = system_kill.pod_kills.max(0) as u32;
+        sample.npc_kills = system_kill.npc_kills.max(0) as u32;
+    }
+    for system_jump in system_jumps {
+        let sample = samples
+            .entry(system_jump.system_id)
+            .or_insert_with(|| empty_sample(system_jump.system_id));
+        sample.jumps = system_jump.ship_jumps.max(0) as u32;
+    }
+
+    samples.into_values().collect()
+}
+
+/// Pulls the last hour of kills and jumps into the activity history, then sets the risk of
+/// every system from its activity over the risk window.
 pub async fn refresh_jump_risks(client: Client, graph: Arc<Graph>) -> Result<(), Error> {
     info!("Refreshing system jump risks");
-    let galaxy_kills = pull_system_kills(client.clone(), graph.clone()).await?;
-    let galaxy_jumps = pull_last_hour_of_jumps(client.clone(), graph.clone()).await?;
-    let system_ids = database::get_all_system_ids(graph.clone()).await?;
-    let mut set = JoinSet::new();
+    let system_kills = pull_system_kills(client.clone(), graph.clone()).await?;
+    let system_jumps = pull_last_hour_of_jumps(client.clone(), graph.clone()).await?;
 
-    let baseline_jump_risk = if galaxy_jumps > 0 {
-        galaxy_kills as f64 / galaxy_jumps as f64
-    } else {
-        0.01 // galaxy jumps should never be zero, but just in case
-    };
+    let hour = last_full_hour();
+    let samples = activity_samples(hour, &system_kills, &system_jumps);
+    database::save_activity_samples(graph.clone(), &samples).await?;
+    let retained_since = hour - activity_retention_hours() * 3600;
+    database::prune_activity_samples(graph.clone(), retained_since).await?;
+    database::prune_kills(graph.clone(), retained_since).await?;
+
+    let window = RiskWindow::configured();
+    let history =
+        database::get_activity_samples(graph.clone(), hour - (window.hours as i64 - 1) * 3600)
+            .await?;
+    let activities = window.decayed_activity(&history, hour);
 
-    system_ids.iter().for_each(|&system_id| {
+    let baseline_jump_risk = baseline_jump_risk(&activities);
+    database::set_baseline_jump_risk(graph.clone(), baseline_jump_risk).await?;
+
+    let systems = database::get_all_systems(graph.clone()).await?;
+    let mut set = JoinSet::new();
+    let mut changes = Vec::with_capacity(systems.len());
+
+    systems.iter().for_each(|system| {
+        let activity = SystemActivity {
+            security_status: system.security_status,
+            ..activities
+                .get(&system.system_id)
+                .copied()
+                .unwrap_or_default()
+        };
+        changes.push(RiskChange {
+            system_id: system.system_id,
+            name: system.name.clone(),
+            previous_risk: system.risk,
+            risk: RiskModelKind::KillsPerJump
+                .model()
+                .risk(&activity, baseline_jump_risk),
+        });
         set.spawn(database::set_system_jump_risk(
             graph.clone(),
-            system_id,
+            system.system_id,
+            activity,
             baseline_jump_risk,
         ));
     });
@@ -317,7 +537,185 @@ pub async fn refresh_jump_risks(client: Client, graph: Arc<Graph>) -> Result<(),
     error_if_any_member_has_error(&mut set)
         .await
         .unwrap()
-        .map_err(Error::Target)
+        .map_err(Error::Target)?;
+
+    events::publish(GraphEvent::RiskRefreshed {
+        most_changed: events::most_changed(changes, MOST_CHANGED_SYSTEMS),
+    });
+    Ok(())
+}
+
+/// Updates the risk of jumping into a system between risk refreshes, counting the kills the live
+/// kill feed saw there over the last hour.
+pub async fn refresh_live_system_risk(graph: Arc<Graph>, system_id: i64) -> Result<(), Error> {
+    let Some(system) = database::get_system(graph.clone(), system_id).await? else {
+        return Ok(());
+    };
+    let now = now_seconds();
+    let hour = last_full_hour();
+    let window = RiskWindow::configured();
+
+    let samples = database::get_system_activity_samples(
+        graph.clone(),
+        system_id,
+        hour - (window.hours as i64 - 1) * 3600,
+    )
+    .await?;
+    let (live_ship_kills, live_pod_kills) =
+        database::get_recent_kill_counts(graph.clone(), system_id, now - 3600).await?;
+    let baseline_jump_risk = database::get_baseline_jump_risk(graph.clone())
+        .await?
+        .unwrap_or(0.01);
+
+    let samples = with_live_kills(&samples, system_id, hour, live_ship_kills, live_pod_kills);
+    let activity = SystemActivity {
+        security_status: system.security_status,
+        ..window
+            .decayed_activity(&samples, hour)
+            .remove(&system_id)
+            .unwrap_or_default()
+    };
+
+    database::set_system_jump_risk(graph, system_id, activity, baseline_jump_risk).await?;
+    Ok(())
+}
+
+/// Records a killmail from the live kill feed and updates the risk of the system it happened in.
+async fn ingest_killmail(graph: Arc<Graph>, killmail: zkillboard::Killmail) -> Result<(), Error> {
+    let kill = database::Kill {
+        killmail_id: killmail.killmail_id,
+        system_id: killmail.solar_system_id,
+        time: killmail.timestamp().unwrap_or_else(now_seconds),
+        ship_type_id: killmail.victim.ship_type_id,
+        pod_kill: killmail.is_pod_kill(),
+        attacker_count: killmail.attackers.len() as u32,
+        position: killmail
+            .victim
+            .position
+            .as_ref()
+            .map(|position| (position.x, position.y, position.z)),
+    };
+    database::save_kill(graph.clone(), &kill).await?;
+    refresh_live_system_risk(graph, kill.system_id).await
+}
+
+/// Listens to the zKillboard RedisQ kill feed for as long as the application runs, recording
+/// every kill and keeping the risk of the systems they happen in current. The `jump-risk`
+/// projection is rebuilt at most once a minute to pick the new risks up.
+pub async fn ingest_kill_feed(client: Client, graph: Arc<Graph>, url: String) {
+    info!("Listening to the zKillboard kill feed at {url}");
+    let mut risk_changed = false;
+    let mut last_projection = Instant::now();
+
+    loop {
+        match zkillboard::listen(&client, &url).await {
+            Ok(Some(killmail)) => {
+                let killmail_id = killmail.killmail_id;
+                match ingest_killmail(graph.clone(), killmail).await {
+                    Ok(()) => risk_changed = true,
+                    Err(e) => warn!("Failed to ingest killmail {killmail_id}: {e}"),
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                warn!("Failed to read the zKillboard kill feed: {e}");
+                tokio::time::sleep(KILL_FEED_RETRY_DELAY).await;
+            }
+        }
+
+        if risk_changed && last_projection.elapsed() >= KILL_FEED_PROJECTION_INTERVAL {
+            match database::refresh_jump_risk_graph(graph.clone()).await {
+                Ok(()) => {
+                    risk_changed = false;
+                    if let Err(e) = evaluate_saved_routes(client.clone(), graph.clone()).await {
+                        warn!("Failed to evaluate saved routes: {e}");
+                    }
+                }
+                Err(e) => warn!("Failed to refresh the jump-risk projection: {e}"),
+            }
+            last_projection = Instant::now();
+        }
+    }
+}
+
+/// Re-evaluates every saved route against the current risks, posting alerts to their webhooks.
+/// Needs an up to date `jump-risk` projection. Routes that can't be evaluated and alerts that
+/// can't be delivered are logged and skipped.
+pub async fn evaluate_saved_routes(client: Client, graph: Arc<Graph>) -> Result<(), Error> {
+    let risk_model = RiskModelKind::configured();
+    let default_webhook_url = alerts::default_webhook_url();
+
+    for saved_route in database::get_saved_routes(graph.clone()).await? {
+        let webhook_url = saved_route
+            .webhook_url
+            .clone()
+            .or_else(|| default_webhook_url.clone());
+        if let Err(e) = evaluate_saved_route(
+            &client,
+            graph.clone(),
+            &saved_route,
+            webhook_url.as_deref(),
+            risk_model,
+        )
+        .await
+        {
+            error!("Failed to evaluate saved route {}: {e}", saved_route.name);
+        }
+    }
+
+    Ok(())
+}
+
+async fn evaluate_saved_route(
+    client: &Client,
+    graph: Arc<Graph>,
+    saved_route: &database::SavedRoute,
+    webhook_url: Option<&str>,
+    risk_model: RiskModelKind,
+) -> Result<(), Error> {
+    let Some(safest_route) = database::find_safest_route(
+        graph.clone(),
+        saved_route.from_system_name.clone(),
+        saved_route.to_system_name.clone(),
+        &database::RouteOptions::default(),
+        risk_model,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    let watched_route = if saved_route.route.is_empty() {
+        safest_route.clone()
+    } else {
+        saved_route.route.clone()
+    };
+    let route_risks =
+        database::get_route_hop_risks(graph.clone(), watched_route, risk_model).await?;
+    let safest_risks =
+        database::get_route_hop_risks(graph.clone(), safest_route.clone(), risk_model).await?;
+
+    let evaluation =
+        alerts::evaluate_saved_route(saved_route, &route_risks, &safest_route, &safest_risks);
+    for alert in &evaluation.alerts {
+        info!("{}", alert.message(saved_route));
+        if let Some(url) = webhook_url
+            && let Err(e) = alerts::send_alert(client, url, saved_route, alert).await
+        {
+            warn!(
+                "Failed to send alert for saved route {}: {e}",
+                saved_route.name
+            );
+        }
+    }
+
+    database::set_saved_route_state(
+        graph,
+        &saved_route.name,
+        evaluation.route,
+        evaluation.hot_systems,
+    )
+    .await?;
+    Ok(())
 }
 
 #[instrument(skip(client, graph), fields(stargate_id = %stargate_id))]
@@ -411,4 +809,34 @@ mod tests {
         assert_eq!(stargate.destination_stargate_id, 50011906);
         assert_eq!(stargate.destination_system_id, 30000144);
     }
+
+    #[test]
+    fn test_activity_samples_combine_kills_and_jumps() {
+        let system_kills: Vec<esi::SystemKills> = serde_json::from_str(
+            r#"[{"system_id": 30002813, "ship_kills": 12, "pod_kills": 9, "npc_kills": 40}]"#,
+        )
+        .unwrap();
+        let system_jumps = vec![
+            esi::SystemJumps {
+                system_id: 30002813,
+                ship_jumps: 300,
+            },
+            esi::SystemJumps {
+                system_id: 30000142,
+                ship_jumps: 5000,
+            },
+        ];
+
+        let mut samples = activity_samples(3600, &system_kills, &system_jumps);
+        samples.sort_by_key(|sample| sample.system_id);
+
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].system_id, 30000142);
+        assert_eq!(samples[0].kills, 0);
+        assert_eq!(samples[0].jumps, 5000);
+        assert_eq!(samples[1].kills, 12);
+        assert_eq!(samples[1].pod_kills, 9);
+        assert_eq!(samples[1].npc_kills, 40);
+        assert_eq!(samples[1].jumps, 300);
+    }
 }
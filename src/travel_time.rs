@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::{Stargate, System};
+use crate::dijkstra::dijkstra;
+
+pub const METERS_PER_AU: f64 = 149_597_870_700.0;
+
+/// Warps end once the ship has slowed down to this speed, in meters per second.
+const WARP_DROPOUT_SPEED: f64 = 100.0;
+
+/// The deceleration rate of a warp is a third of the warp speed, but never more than this.
+const MAX_WARP_DECELERATION: f64 = 2.0;
+
+/// The time it takes to activate a stargate and load the next system.
+const GATE_JUMP_SECONDS: f64 = 10.0;
+
+/// Used when where the ship warps from or to isn't known, such as at the start of the route or
+/// after coming through a wormhole.
+const UNKNOWN_WARP_DISTANCE_AU: f64 = 15.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ship {
+    Shuttle,
+    Interceptor,
+    Frigate,
+    Destroyer,
+    Cruiser,
+    Battlecruiser,
+    Battleship,
+    Industrial,
+    BlockadeRunner,
+    DeepSpaceTransport,
+    Freighter,
+    JumpFreighter,
+}
+
+impl Ship {
+    pub const ALL: [Ship; 12] = [
+        Ship::Shuttle,
+        Ship::Interceptor,
+        Ship::Frigate,
+        Ship::Destroyer,
+        Ship::Cruiser,
+        Ship::Battlecruiser,
+        Ship::Battleship,
+        Ship::Industrial,
+        Ship::BlockadeRunner,
+        Ship::DeepSpaceTransport,
+        Ship::Freighter,
+        Ship::JumpFreighter,
+    ];
+
+    /// Typical values for a ship of this class.
+    pub fn profile(&self) -> ShipProfile {
+        let (warp_speed, align_time) = match self {
+            Ship::Shuttle => (8.0, 2.5),
+            Ship::Interceptor => (8.0, 2.0),
+            Ship::Frigate => (5.0, 3.5),
+            Ship::Destroyer => (4.5, 5.0),
+            Ship::Cruiser => (3.0, 7.0),
+            Ship::Battlecruiser => (2.7, 9.0),
+            Ship::Battleship => (2.0, 12.0),
+            Ship::Industrial => (4.5, 10.0),
+            Ship::BlockadeRunner => (4.5, 5.0),
+            Ship::DeepSpaceTransport => (4.5, 12.0),
+            Ship::Freighter => (1.37, 40.0),
+            Ship::JumpFreighter => (1.37, 45.0),
+        };
+        ShipProfile {
+            warp_speed,
+            align_time,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShipProfile {
+    /// Maximum warp speed in AU per second.
+    pub warp_speed: f64,
+    /// Seconds to align and enter warp.
+    pub align_time: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct TravelTime {
+    pub total_seconds: f64,
+    pub align_seconds: f64,
+    pub warp_seconds: f64,
+    pub gate_seconds: f64,
+}
+
+impl TravelTime {
+    fn add_warp(&mut self, distance: f64, ship: &ShipProfile) {
+        let warp = warp_seconds(distance, ship.warp_speed);
+        self.align_seconds += ship.align_time;
+        self.warp_seconds += warp;
+        self.total_seconds += ship.align_time + warp;
+    }
+
+    fn add_gate_jump(&mut self) {
+        self.gate_seconds += GATE_JUMP_SECONDS;
+        self.total_seconds += GATE_JUMP_SECONDS;
+    }
+}
+
+/// The time spent in warp over a distance in meters, following the game's warp mechanics. The
+/// ship accelerates at a rate equal to its warp speed in AU/s and decelerates at a third of that
+/// rate, up to 2, until it drops out at 100 m/s. Short warps never reach full speed.
+pub fn warp_seconds(distance: f64, warp_speed: f64) -> f64 {
+    let acceleration = warp_speed;
+    let deceleration = (warp_speed / 3.0).min(MAX_WARP_DECELERATION);
+    let max_speed = warp_speed * METERS_PER_AU;
+
+    let acceleration_distance = max_speed / acceleration;
+    let deceleration_distance = max_speed / deceleration;
+    let (peak_speed, cruise_seconds) = if distance < acceleration_distance + deceleration_distance {
+        let peak = distance * acceleration * deceleration / (acceleration + deceleration);
+        (peak, 0.0)
+    } else {
+        let cruise = (distance - acceleration_distance - deceleration_distance) / max_speed;
+        (max_speed, cruise)
+    };
+
+    let acceleration_seconds = peak_speed.max(1.0).ln() / acceleration;
+    let deceleration_seconds = (peak_speed / WARP_DROPOUT_SPEED).max(1.0).ln() / deceleration;
+    acceleration_seconds + cruise_seconds + deceleration_seconds
+}
+
+fn distance_between(from: &Stargate, to: &Stargate) -> f64 {
+    let dx = from.x - to.x;
+    let dy = from.y - to.y;
+    let dz = from.z - to.z;
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+/// Estimates how long a route takes to fly. Each hop is given as the stargate taken out of a
+/// system and the one arrived at in the next, or None where there is no stargate, such as for a
+/// wormhole. The pilot is assumed to start somewhere in space in the first system.
+pub fn estimate_travel_time(
+    hops: &[Option<(Stargate, Stargate)>],
+    ship: &ShipProfile,
+) -> TravelTime {
+    let unknown_distance = UNKNOWN_WARP_DISTANCE_AU * METERS_PER_AU;
+    let mut travel_time = TravelTime::default();
+    let mut arrived_at: Option<&Stargate> = None;
+
+    for hop in hops {
+        let distance = match (arrived_at, hop) {
+            (Some(arrival), Some((departure, _))) => distance_between(arrival, departure),
+            _ => unknown_distance,
+        };
+        travel_time.add_warp(distance, ship);
+        travel_time.add_gate_jump();
+        arrived_at = hop.as_ref().map(|(_, arrival)| arrival);
+    }
+
+    travel_time
+}
+
+#[derive(Debug, Serialize)]
+pub struct FastestRoute {
+    pub route: Vec<String>,
+    pub travel_time: TravelTime,
+}
+
+/// Finds the route through stargates that takes the least time to fly. Since the time spent in a
+/// system depends on which gate the ship came in through, the search runs over the gates arrived
+/// at rather than over systems.
+pub fn plan_fastest_route(
+    systems: &[System],
+    stargates: &[Stargate],
+    from_system_name: &str,
+    to_system_name: &str,
+    ship: &ShipProfile,
+) -> Option<FastestRoute> {
+    let source = systems.iter().find(|s| s.name == from_system_name)?;
+    let target = systems.iter().find(|s| s.name == to_system_name)?;
+    let names: HashMap<i64, &str> = systems
+        .iter()
+        .map(|s| (s.system_id, s.name.as_str()))
+        .collect();
+    if source.system_id == target.system_id {
+        return Some(FastestRoute {
+            route: vec![source.name.clone()],
+            travel_time: TravelTime::default(),
+        });
+    }
+
+    let index_by_id: HashMap<i64, usize> = stargates
+        .iter()
+        .enumerate()
+        .map(|(index, gate)| (gate.stargate_id, index))
+        .collect();
+    let mut gates_in_system: HashMap<i64, Vec<usize>> = HashMap::new();
+    for (index, gate) in stargates.iter().enumerate() {
+        gates_in_system
+            .entry(gate.system_id)
+            .or_default()
+            .push(index);
+    }
+
+    // The search starts from a node standing for the source system itself, before any gate has
+    // been arrived at. From there each gate in the system can be warped to from an unknown spot.
+    let start = stargates.len();
+    let path = dijkstra(
+        stargates.len() + 1,
+        start,
+        TravelTime::default(),
+        |gate| gate != start && stargates[gate].system_id == target.system_id,
+        |travel_time| travel_time.total_seconds,
+        |gate, travel_time| {
+            let system_id = if gate == start {
+                source.system_id
+            } else {
+                stargates[gate].system_id
+            };
+            let Some(departures) = gates_in_system.get(&system_id) else {
+                return Vec::new();
+            };
+            departures
+                .iter()
+                .filter(|&&departure| departure != gate)
+                .filter_map(|&departure| {
+                    let arrival =
+                        *index_by_id.get(&stargates[departure].destination_stargate_id)?;
+                    let distance = if gate == start {
+                        UNKNOWN_WARP_DISTANCE_AU * METERS_PER_AU
+                    } else {
+                        distance_between(&stargates[gate], &stargates[departure])
+                    };
+                    let mut travel_time = *travel_time;
+                    travel_time.add_warp(distance, ship);
+                    travel_time.add_gate_jump();
+                    Some((arrival, departure, travel_time))
+                })
+                .collect()
+        },
+    )?;
+
+    let mut route = vec![source.name.clone()];
+    for (_, _, arrival) in &path.steps {
+        route.push(names.get(&stargates[*arrival].system_id)?.to_string());
+    }
+
+    Some(FastestRoute {
+        route,
+        travel_time: path.label,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_warp_never_reaches_full_speed() {
+        // A 150 km warp for a 3 AU/s cruiser peaks at 150 km * 3 * 1 / 4 = 112.5 km/s, taking
+        // ln(112500) / 3 s to speed up and ln(1125) / 1 s to slow down.
+        let seconds = warp_seconds(150_000.0, 3.0);
+        let expected = 112_500f64.ln() / 3.0 + 1_125f64.ln();
+        assert!((seconds - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_long_warp_cruises_at_full_speed() {
+        // Every extra AU at full speed adds 1 / warp speed seconds.
+        let ten = warp_seconds(10.0 * METERS_PER_AU, 5.0);
+        let twenty = warp_seconds(20.0 * METERS_PER_AU, 5.0);
+        assert!((twenty - ten - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_freighters_are_slower_than_frigates() {
+        let distance = 30.0 * METERS_PER_AU;
+        let freighter = warp_seconds(distance, Ship::Freighter.profile().warp_speed);
+        let frigate = warp_seconds(distance, Ship::Frigate.profile().warp_speed);
+        assert!(freighter > frigate);
+    }
+
+    fn system(system_id: i64, name: &str) -> System {
+        System {
+            name: name.to_string(),
+            security_status: 0.9,
+            system_id,
+            ..System::default()
+        }
+    }
+
+    /// A pair of gates linking two systems, each placed some AU from the origin of its system.
+    fn gate_pair(id: i64, from: (i64, f64), to: (i64, f64)) -> [Stargate; 2] {
+        let gate =
+            |stargate_id, system_id, au: f64, destination_stargate_id, destination_system_id| {
+                Stargate {
+                    destination_stargate_id,
+                    destination_system_id,
+                    name: format!("Stargate {stargate_id}"),
+                    x: au * METERS_PER_AU,
+                    y: 0.0,
+                    z: 0.0,
+                    stargate_id,
+                    system_id,
+                    type_id: 16,
+                }
+            };
+        [
+            gate(id, from.0, from.1, id + 1, to.0),
+            gate(id + 1, to.0, to.1, id, from.0),
+        ]
+    }
+
+    #[test]
+    fn test_fastest_route_avoids_long_warps() {
+        // A -> B -> D has fewer jumps, but the gates in B are 200 AU apart. A -> C -> E -> D has
+        // gates right next to each other.
+        let systems = vec![
+            system(1, "A"),
+            system(2, "B"),
+            system(3, "C"),
+            system(4, "D"),
+            system(5, "E"),
+        ];
+        let stargates: Vec<Stargate> = [
+            gate_pair(10, (1, 0.0), (2, 0.0)),
+            gate_pair(20, (2, 200.0), (4, 0.0)),
+            gate_pair(30, (1, 0.0), (3, 0.0)),
+            gate_pair(40, (3, 0.1), (5, 0.0)),
+            gate_pair(50, (5, 0.1), (4, 0.0)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let fastest =
+            plan_fastest_route(&systems, &stargates, "A", "D", &Ship::Freighter.profile()).unwrap();
+
+        assert_eq!(fastest.route, vec!["A", "C", "E", "D"]);
+        assert_eq!(fastest.travel_time.gate_seconds, 3.0 * GATE_JUMP_SECONDS);
+    }
+}
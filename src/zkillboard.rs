@@ -0,0 +1,158 @@
+use std::env;
+
+use chrono::DateTime;
+use reqwest::{Client, Response};
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+use tracing::error;
+
+/// The ship types of capsules, whose losses count as pod kills.
+const CAPSULE_TYPE_IDS: [i64; 2] = [670, 33328];
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Request to zKillboard RedisQ failed")]
+    Request(#[from] reqwest::Error),
+    #[error("zKillboard RedisQ server error ({status}): {body}")]
+    ServerError { status: u16, body: String },
+    #[error("Unexpected zKillboard RedisQ error ({status}): {body}")]
+    UnexpectedError { status: u16, body: String },
+}
+
+#[derive(Debug, Deserialize)]
+struct RedisQResponse {
+    package: Option<Package>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Package {
+    killmail: Killmail,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Killmail {
+    pub killmail_id: i64,
+    pub killmail_time: String,
+    pub solar_system_id: i64,
+    pub victim: Victim,
+    pub attackers: Vec<IgnoredAny>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Victim {
+    pub ship_type_id: i64,
+    pub position: Option<Position>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Killmail {
+    pub fn is_pod_kill(&self) -> bool {
+        CAPSULE_TYPE_IDS.contains(&self.victim.ship_type_id)
+    }
+
+    /// When the kill happened, in seconds since the epoch.
+    pub fn timestamp(&self) -> Option<i64> {
+        parse_killmail_time(&self.killmail_time)
+    }
+}
+
+/// The RedisQ URL to listen to, set with the `ZKILLBOARD_REDISQ_URL` environment variable. The
+/// kill feed is only ingested when it's set.
+pub fn configured_url() -> Option<String> {
+    env::var("ZKILLBOARD_REDISQ_URL")
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+}
+
+/// Long-polls RedisQ for the next killmail. RedisQ answers with an empty package when no kill came
+/// in while it waited.
+pub async fn listen(client: &Client, url: &str) -> Result<Option<Killmail>, Error> {
+    let response = client.get(url).send().await?;
+    let response: RedisQResponse = process_redisq_response(response).await?;
+    Ok(response.package.map(|package| package.killmail))
+}
+
+async fn process_redisq_response<T: for<'de> Deserialize<'de>>(
+    response: Response,
+) -> Result<T, Error> {
+    let status = response.status();
+    let url = response.url().clone();
+
+    if status.is_success() {
+        return response.json::<T>().await.map_err(Error::Request);
+    }
+
+    let body = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Could not read error body".to_string());
+    error!(
+        "zKillboard RedisQ request to {} failed with status {}: {}",
+        url, status, body
+    );
+
+    match status.as_u16() {
+        500..=599 => Err(Error::ServerError {
+            status: status.as_u16(),
+            body,
+        }),
+        _ => Err(Error::UnexpectedError {
+            status: status.as_u16(),
+            body,
+        }),
+    }
+}
+
+/// Parses a killmail time like `2024-05-01T18:32:07Z` into seconds since the epoch.
+fn parse_killmail_time(time: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(time)
+        .ok()
+        .map(|time| time.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_killmail_time() {
+        assert_eq!(parse_killmail_time("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_killmail_time("2024-02-29T12:30:15Z"),
+            Some(1709209815)
+        );
+        assert_eq!(parse_killmail_time("not a time"), None);
+    }
+
+    #[test]
+    fn test_redisq_package_deserializes_killmail() {
+        let body = r#"{"package": {"killID": 118000000, "killmail": {
+            "killmail_id": 118000000, "killmail_time": "2024-05-01T18:32:07Z",
+            "solar_system_id": 30002813,
+            "victim": {"ship_type_id": 670, "position": {"x": 1.0, "y": 2.0, "z": 3.0}},
+            "attackers": [{"ship_type_id": 11377}, {"ship_type_id": 11377}]
+        }, "zkb": {"totalValue": 10000.0}}}"#;
+
+        let killmail = serde_json::from_str::<RedisQResponse>(body)
+            .unwrap()
+            .package
+            .unwrap()
+            .killmail;
+
+        assert_eq!(killmail.solar_system_id, 30002813);
+        assert_eq!(killmail.attackers.len(), 2);
+        assert!(killmail.is_pod_kill());
+        assert!(
+            serde_json::from_str::<RedisQResponse>(r#"{"package": null}"#)
+                .unwrap()
+                .package
+                .is_none()
+        );
+    }
+}